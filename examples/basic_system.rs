@@ -17,35 +17,40 @@ fn main() {
         println!("{}", "=".repeat(80));
         
         // Print detailed star information
-        println!("Star System: {}", system.star.name);
-        println!("Stellar Type: {:?}", system.star.stellar_type);
-        println!("Mass: {:.2} solar masses", system.star.physical.mass / 1.989e30);
-        println!("Radius: {:.2} solar radii", system.star.physical.radius / 6.957e8);
-        println!("Surface Temperature: {:.0}K", system.star.physical.surface_temperature);
-        println!("Luminosity: {:.2e} solar luminosity", system.star.luminosity);
+        println!("Star System: {}", system.star().name);
+        println!("Stellar Type: {:?}", system.star().stellar_type);
+        println!("Mass: {:.2} solar masses", system.star().physical.mass / 1.989e30);
+        println!("Radius: {:.2} solar radii", system.star().physical.radius / 6.957e8);
+        println!("Surface Temperature: {:.0}K", system.star().physical.surface_temperature);
+        println!("Luminosity: {:.2e} solar luminosity", system.star().luminosity);
         println!("Age: {:.2} billion years", system.system_age);
-        println!("Magnetic Field: {:.2e} Tesla", system.star.magnetic_field);
-        println!("Rotation Period: {:.2} Earth days", system.star.rotation_period);
+        println!("Magnetic Field: {:.2e} Tesla", system.star().magnetic_field);
+        println!("Rotation Period: {:.2} Earth days", system.star().rotation_period);
+        for (companion, orbit) in system.stars[1..].iter().zip(&system.companion_orbits) {
+            println!("Companion: {:?}, {:.2} solar masses at {:.2} AU (e = {:.2})",
+                companion.stellar_type, companion.physical.mass / 1.989e30,
+                orbit.semi_major_axis, orbit.eccentricity);
+        }
         
         // Print composition
         println!("\nStellar Composition:");
-        println!("Hydrogen: {:.2}%", system.star.composition.hydrogen * 100.0);
-        println!("Helium: {:.2}%", system.star.composition.helium * 100.0);
-        println!("Metallicity: {:.2}%", system.star.composition.metallicity * 100.0);
-        println!("Other: {:.2}%", system.star.composition.other * 100.0);
+        println!("Hydrogen: {:.2}%", system.star().composition.hydrogen * 100.0);
+        println!("Helium: {:.2}%", system.star().composition.helium * 100.0);
+        println!("Metallicity: {:.2}%", system.star().composition.metallicity * 100.0);
+        println!("Other: {:.2}%", system.star().composition.other * 100.0);
 
         // Print special characteristics based on stellar type
         println!("\nSpecial Characteristics:");
-        match system.star.stellar_type {
+        match system.star().stellar_type {
             StellarType::BlackHole => {
-                let schwarzschild_radius = 2.0 * 6.674e-11 * system.star.physical.mass / (299_792_458.0f64.powi(2));
+                let schwarzschild_radius = 2.0 * 6.674e-11 * system.star().physical.mass / (299_792_458.0f64.powi(2));
                 println!("Event Horizon Radius: {:.2e} meters", schwarzschild_radius);
                 println!("Extreme Gravitational Effects");
                 println!("No Habitable Zone Possible");
             },
             StellarType::NeutronStar | StellarType::PulsarStar | StellarType::MagnetarStar => {
                 println!("Extreme Density: ~10¹⁴ g/cm³");
-                println!("Magnetic Field Strength: {:.2e} Tesla", system.star.magnetic_field);
+                println!("Magnetic Field Strength: {:.2e} Tesla", system.star().magnetic_field);
                 println!("Intense Radiation Environment");
             },
             StellarType::WhiteDwarfRemnant => {
@@ -59,11 +64,11 @@ fn main() {
                 println!("Minimal Fusion Processes");
             },
             _ => {
-                if system.star.physical.surface_temperature > 10000.0 {
+                if system.star().physical.surface_temperature > 10000.0 {
                     println!("Strong Stellar Wind");
                     println!("Significant UV Radiation");
                 }
-                if matches!(system.star.stellar_type, 
+                if matches!(system.star().stellar_type, 
                     StellarType::RedGiant | StellarType::SuperGiant | StellarType::HyperGiant) {
                     println!("Extended Atmosphere");
                     println!("Significant Mass Loss");
@@ -71,18 +76,18 @@ fn main() {
             }
         }
 
-        if system.star.stellar_type.can_have_planets() {
+        if system.star().stellar_type.can_have_planets() {
             println!("\nHabitable Zone: {:.2} AU to {:.2} AU", system.habitable_zone.0, system.habitable_zone.1);
             
             // Print information about planets
             println!("\nPlanets: {}", system.planets.len());
             for (i, planet) in system.planets.iter().enumerate() {
-                let distance = (planet.position.x.powi(2) + planet.position.y.powi(2)).sqrt() / 1.496e11;
+                let distance = (planet.position().x.powi(2) + planet.position().y.powi(2)).sqrt() / 1.496e11;
                 println!("\nPlanet {}: {}", i + 1, planet.name);
                 println!("Type: {:?}", planet.planet_type);
                 println!("Mass: {:.2} Earth masses", planet.physical.mass / 5.972e24);
                 println!("Distance from star: {:.2} AU", distance);
-                println!("Orbital Period: {:.2} Earth years", planet.orbital_period);
+                println!("Orbital Period: {:.2} Earth years", planet.orbital_period());
                 println!("Surface Temperature: {:.0}K", planet.physical.surface_temperature);
                 println!("Potentially Habitable: {}", planet.habitable);
             }
@@ -91,7 +96,7 @@ fn main() {
             let habitable = system.habitable_planets();
             println!("\nNumber of potentially habitable planets: {}", habitable.len());
             for planet in habitable {
-                let distance = (planet.position.x.powi(2) + planet.position.y.powi(2)).sqrt() / 1.496e11;
+                let distance = (planet.position().x.powi(2) + planet.position().y.powi(2)).sqrt() / 1.496e11;
                 println!("- {} ({:?}) at {:.2} AU", planet.name, planet.planet_type, distance);
             }
 
@@ -100,7 +105,7 @@ fn main() {
             let small_bodies = system.generate_small_bodies(main_belt_center, 0.5, 10.0);
             println!("\nMain Belt Objects: {}", small_bodies.len());
             for (i, body) in small_bodies.iter().take(5).enumerate() {
-                let distance = (body.position.x.powi(2) + body.position.y.powi(2)).sqrt() / 1.496e11;
+                let distance = (body.position().x.powi(2) + body.position().y.powi(2)).sqrt() / 1.496e11;
                 println!("\nAsteroid {}: {}", i + 1, body.name);
                 println!("Type: {:?}", body.body_type);
                 println!("Mass: {:.2e} kg", body.physical.mass);
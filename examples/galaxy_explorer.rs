@@ -29,6 +29,10 @@ fn main() {
         
         if region.population == PopulationType::ThinDisk {
             println!("Spiral Phase: {:.2}π", region.spiral_phase / std::f64::consts::PI);
+            match region.arm_index {
+                Some(arm) => println!("Spiral Arm: {} (young stars: {:.1}%)", arm + 1, region.young_star_fraction * 100.0),
+                None => println!("Inter-arm region"),
+            }
         }
 
         // Generate some example systems in this region
@@ -37,8 +41,8 @@ fn main() {
             let seed = ((x + y + z) as u64).wrapping_mul(1000).wrapping_add(i);
             if let Some(system) = region.generate_solar_system(seed) {
                 println!("\nStar System {}:", i + 1);
-                println!("Star Type: {:?}", system.star.stellar_type);
-                println!("Mass: {:.2} solar masses", system.star.physical.mass / 1.989e30);
+                println!("Star Type: {:?}", system.star().stellar_type);
+                println!("Mass: {:.2} solar masses", system.star().physical.mass / 1.989e30);
                 println!("Planets: {}", system.planets.len());
                 
                 // Show habitable planets if any
@@ -0,0 +1,220 @@
+//! Atmospheric chemistry and escape.
+//!
+//! Giants keep the hydrogen and helium they captured from the nebula. Solid
+//! worlds start from the gases their interiors outgas and lose those light
+//! enough to escape to space, judged by the Jeans parameter at the exobase.
+
+use serde::{Deserialize, Serialize};
+
+const BOLTZMANN: f64 = 1.380649e-23;    // J/K
+const ATOMIC_MASS_UNIT: f64 = 1.66054e-27;  // kg
+
+/// Jeans parameter below which a gas escapes over billions of years: an escape
+/// velocity six times the thermal rms speed (Jeans 1925; Hunten 1973)
+pub const JEANS_RETENTION_THRESHOLD: f64 = 54.0;
+
+/// Hottest thermosphere (K); above it hydrogen emission radiates away extra heating
+const MAX_EXOSPHERE_TEMPERATURE: f64 = 10000.0;
+
+/// Mole fractions of the gases making up an atmosphere, summing to one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AtmosphericComposition {
+    pub nitrogen: f64,        // N2
+    pub oxygen: f64,          // O2
+    pub carbon_dioxide: f64,  // CO2
+    pub water: f64,           // H2O
+    pub methane: f64,         // CH4
+    pub ammonia: f64,         // NH3
+    pub argon: f64,           // Ar
+    pub hydrogen: f64,        // H2
+    pub helium: f64,          // He
+    pub sulfur_dioxide: f64,  // SO2
+}
+
+impl AtmosphericComposition {
+    /// Molecular masses (amu) in field order
+    const MOLECULAR_MASSES: [f64; 10] = [28.0, 32.0, 44.0, 18.0, 16.0, 17.0, 40.0, 2.0, 4.0, 64.0];
+
+    fn fractions(&self) -> [f64; 10] {
+        [
+            self.nitrogen, self.oxygen, self.carbon_dioxide, self.water, self.methane,
+            self.ammonia, self.argon, self.hydrogen, self.helium, self.sulfur_dioxide,
+        ]
+    }
+
+    fn from_fractions(f: [f64; 10]) -> Self {
+        AtmosphericComposition {
+            nitrogen: f[0],
+            oxygen: f[1],
+            carbon_dioxide: f[2],
+            water: f[3],
+            methane: f[4],
+            ammonia: f[5],
+            argon: f[6],
+            hydrogen: f[7],
+            helium: f[8],
+            sulfur_dioxide: f[9],
+        }
+    }
+
+    /// Hydrogen and helium envelope of a giant planet; `ice_rich` envelopes
+    /// (Uranus and Neptune) carry several times more methane and water. Above
+    /// ~1000 K carbon sits in carbon monoxide, which is not tracked, and ammonia
+    /// breaks down, leaving water as the main trace gas.
+    pub fn envelope(ice_rich: bool, temperature: f64) -> Self {
+        let mut composition = if ice_rich {
+            AtmosphericComposition { hydrogen: 0.80, helium: 0.17, methane: 0.02, water: 0.01, ammonia: 1e-4, ..Default::default() }
+        } else {
+            AtmosphericComposition { hydrogen: 0.862, helium: 0.136, methane: 0.0018, water: 1e-4, ammonia: 2.6e-4, ..Default::default() }
+        };
+        if temperature > 1000.0 {
+            composition.methane = 0.0;
+            composition.ammonia = 0.0;
+        }
+        composition.normalized()
+    }
+
+    /// Venus: the crust's carbon dioxide released after the oceans boiled away
+    pub fn runaway_greenhouse() -> Self {
+        AtmosphericComposition {
+            carbon_dioxide: 0.965, nitrogen: 0.035, sulfur_dioxide: 1.5e-4, argon: 7e-5, water: 2e-5,
+            ..Default::default()
+        }
+    }
+
+    /// Mars: a thin remnant that cannot keep oceans to draw down carbon dioxide
+    pub fn thin() -> Self {
+        AtmosphericComposition {
+            carbon_dioxide: 0.95, nitrogen: 0.028, argon: 0.019, oxygen: 0.0017, water: 3e-4,
+            ..Default::default()
+        }
+    }
+
+    /// A lifeless Earth: nitrogen with carbon dioxide held in check by
+    /// weathering; water vapour is added for the surface temperature
+    pub fn temperate() -> Self {
+        AtmosphericComposition { nitrogen: 0.986, carbon_dioxide: 0.004, argon: 0.01, ..Default::default() }
+    }
+
+    /// Fractions rescaled to sum to one
+    pub fn normalized(&self) -> Self {
+        let total: f64 = self.fractions().iter().sum();
+        if total <= 0.0 {
+            return *self;
+        }
+        Self::from_fractions(self.fractions().map(|f| f / total))
+    }
+
+    /// This gas with `fraction` of its molecules replaced by `other`
+    pub fn mixed(&self, other: &AtmosphericComposition, fraction: f64) -> Self {
+        let (mine, theirs) = (self.fractions(), other.fractions());
+        Self::from_fractions(std::array::from_fn(|i| (1.0 - fraction) * mine[i] + fraction * theirs[i]))
+    }
+
+    /// Mean molecular mass (amu)
+    pub fn mean_molecular_mass(&self) -> f64 {
+        self.fractions().iter().zip(Self::MOLECULAR_MASSES).map(|(f, m)| f * m).sum()
+    }
+
+    /// Fraction of the gas absorbing thermal infrared: the polyatomic
+    /// greenhouse gases, plus hydrogen through collision-induced absorption
+    pub fn greenhouse_fraction(&self) -> f64 {
+        self.carbon_dioxide + self.water + self.methane + self.ammonia + self.sulfur_dioxide + self.hydrogen
+    }
+
+    /// Remove every gas whose Jeans parameter falls below the retention threshold
+    /// and return the fraction of molecules kept
+    pub fn escape(&mut self, escape_velocity: f64, exosphere_temperature: f64) -> f64 {
+        let mut kept = self.fractions();
+        for (fraction, mass) in kept.iter_mut().zip(Self::MOLECULAR_MASSES) {
+            if jeans_parameter(escape_velocity, exosphere_temperature, mass) < JEANS_RETENTION_THRESHOLD {
+                *fraction = 0.0;
+            }
+        }
+        let retained = kept.iter().sum();
+        *self = Self::from_fractions(kept).normalized();
+        retained
+    }
+}
+
+/// Jeans escape parameter λ = v_esc² / v₀²: the ratio of gravitational binding
+/// to thermal energy for a molecule of `molecular_mass` amu, where v₀ is the most
+/// probable thermal speed at the exobase. Escape velocity in m/s.
+pub fn jeans_parameter(escape_velocity: f64, exosphere_temperature: f64, molecular_mass: f64) -> f64 {
+    let thermal_speed_squared = 2.0 * BOLTZMANN * exosphere_temperature / (molecular_mass * ATOMIC_MASS_UNIT);
+    escape_velocity.powi(2) / thermal_speed_squared
+}
+
+/// Whether water vapour is bound against Jeans escape; without it the
+/// vapour above oceans is lost to space
+pub fn retains_water(escape_velocity: f64, exosphere_temperature: f64) -> bool {
+    jeans_parameter(escape_velocity, exosphere_temperature, 18.0) >= JEANS_RETENTION_THRESHOLD
+}
+
+/// Exobase temperature (K) of an upper atmosphere heated by ultraviolet flux
+/// (Earth = 1) above the planet's equilibrium temperature; Earth's ~1000 K
+/// thermosphere is four times its equilibrium temperature
+pub fn exosphere_temperature(equilibrium_temperature: f64, ultraviolet_flux: f64) -> f64 {
+    (equilibrium_temperature * (1.0 + 3.0 * ultraviolet_flux.max(0.0).sqrt())).min(MAX_EXOSPHERE_TEMPERATURE)
+}
+
+/// Vapour pressure (bar) over liquid water or ice at `temperature` K, from the
+/// Clausius–Clapeyron relation anchored at the triple point
+pub fn saturation_vapour_pressure(temperature: f64) -> f64 {
+    6.11e-3 * (5423.0 * (1.0 / 273.15 - 1.0 / temperature.max(1.0))).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jeans_escape() {
+        // Earth's ~1000 K exosphere leaks hydrogen and helium but keeps its air
+        let light = AtmosphericComposition { hydrogen: 0.8, helium: 0.2, ..Default::default() };
+        let mut earth = AtmosphericComposition::temperate().mixed(&light, 0.025);
+        let kept = earth.escape(11186.0, exosphere_temperature(255.0, 1.0));
+        assert!((kept - 0.975).abs() < 1e-9);
+        assert_eq!(earth.hydrogen + earth.helium, 0.0);
+        assert!((earth.nitrogen + earth.carbon_dioxide + earth.argon - 1.0).abs() < 1e-9);
+        assert!(retains_water(11186.0, 1000.0));
+
+        // Mars loses water vapour but holds on to carbon dioxide and nitrogen
+        let mars_exosphere = exosphere_temperature(210.0, 1.0 / 1.524f64.powi(2));
+        assert!(!retains_water(5027.0, mars_exosphere));
+        assert!(jeans_parameter(5027.0, mars_exosphere, 28.0) > JEANS_RETENTION_THRESHOLD);
+
+        // Jupiter binds even hydrogen
+        assert!(jeans_parameter(59500.0, 1000.0, 2.0) > JEANS_RETENTION_THRESHOLD);
+    }
+
+    #[test]
+    fn test_compositions() {
+        for composition in [
+            AtmosphericComposition::envelope(false, 120.0),
+            AtmosphericComposition::envelope(true, 70.0),
+            AtmosphericComposition::envelope(false, 1500.0),
+            AtmosphericComposition::runaway_greenhouse().normalized(),
+            AtmosphericComposition::thin().normalized(),
+            AtmosphericComposition::temperate(),
+        ] {
+            let total: f64 = composition.fractions().iter().sum();
+            assert!((total - 1.0).abs() < 1e-9, "{:?}", composition);
+        }
+
+        // Mean molecular masses of Jupiter, the Earth's dry air and Venus
+        assert!((AtmosphericComposition::envelope(false, 120.0).mean_molecular_mass() - 2.3).abs() < 0.1);
+        assert!((AtmosphericComposition::temperate().mean_molecular_mass() - 28.4).abs() < 0.5);
+        assert!((AtmosphericComposition::runaway_greenhouse().mean_molecular_mass() - 43.4).abs() < 0.5);
+
+        // Hot Jupiters hold their carbon in carbon monoxide rather than methane
+        let hot = AtmosphericComposition::envelope(false, 1500.0);
+        let cool = AtmosphericComposition::envelope(false, 120.0);
+        assert_eq!(hot.methane, 0.0);
+        assert!((hot.water / hot.hydrogen - cool.water / cool.hydrogen).abs() < 1e-12);
+        assert!(AtmosphericComposition::envelope(true, 70.0).methane > 0.01);
+
+        // Earth's surface vapour pressure is ~17 mbar at 15 °C
+        assert!((saturation_vapour_pressure(288.0) - 0.017).abs() < 0.002);
+    }
+}
@@ -0,0 +1,316 @@
+//! Binary and hierarchical multiple star systems.
+//!
+//! Companions are stacked hierarchically: `stars[k]` orbits the barycentre of
+//! `stars[..k]` on `SolarSystem::companion_orbits[k - 1]`. Planets either orbit
+//! a single component (S-type) or circle the inner pair (P-type).
+
+use serde::{Deserialize, Serialize};
+use rand::prelude::*;
+use rand_distr::Normal;
+use std::f64::consts::PI;
+use crate::{Orbit, Star};
+use crate::seed::SeedPath;
+
+/// What a planet orbits in a multiple star system
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PlanetHost {
+    Star(usize),   // S-type: a single star, by index into `SolarSystem::stars`
+    InnerBinary,   // P-type: circumbinary, around the barycentre of stars 0 and 1
+}
+
+/// Fraction of stars of the given primary mass (solar masses) with at least one companion
+///
+/// From ~20% for brown dwarfs through ~45% for Sun-like stars to ~70% for O/B
+/// stars (Raghavan et al. 2010; Duchêne & Kraus 2013)
+pub fn multiplicity_fraction(primary_mass: f64) -> f64 {
+    match primary_mass {
+        m if m < 0.08 => 0.2,
+        m if m < 0.5 => 0.27,
+        m if m < 1.5 => 0.46,
+        m if m < 5.0 => 0.6,
+        _ => 0.7,
+    }
+}
+
+/// Outer edge (AU) of stable S-type orbits around one component of a binary
+/// with separation `separation` AU, eccentricity `e` and companion mass ratio
+/// `mu` = m_companion / (m_host + m_companion) (Holman & Wiegert 1999)
+pub fn s_type_stability_limit(separation: f64, e: f64, mu: f64) -> f64 {
+    separation
+        * (0.464 - 0.380 * mu - 0.631 * e + 0.586 * mu * e + 0.150 * e * e - 0.198 * mu * e * e)
+            .max(0.0)
+}
+
+/// Inner edge (AU) of stable P-type orbits around both components of a binary,
+/// with `mu` = m_secondary / (m_primary + m_secondary) (Holman & Wiegert 1999)
+pub fn p_type_stability_limit(separation: f64, e: f64, mu: f64) -> f64 {
+    separation
+        * (1.60 + 5.10 * e - 2.22 * e * e + 4.12 * mu - 4.27 * e * mu - 5.09 * mu * mu
+            + 4.61 * e * e * mu * mu)
+}
+
+/// Smallest ratio of an outer orbit's periapsis to the inner separation for a
+/// stable hierarchical triple, with outer mass ratio `q_out` = m_outer / m_inner
+/// (Mardling & Aarseth 2001)
+pub fn triple_stability_ratio(q_out: f64, e_out: f64) -> f64 {
+    2.8 * ((1.0 + q_out) * (1.0 + e_out)).powf(0.4) * (1.0 - e_out).powf(-0.2)
+}
+
+/// Draw the companions of `primary`, returning every star (primary first) and
+/// the orbit of each companion around the stars before it
+pub(crate) fn generate_companions(path: &SeedPath, primary: Star) -> (Vec<Star>, Vec<Orbit>) {
+    let mut rng = path.stream("multiplicity").rng();
+    let primary_mass = primary.physical.mass / 1.989e30;
+    let mut stars = vec![primary];
+    let mut orbits = Vec::new();
+
+    if rng.gen::<f64>() >= multiplicity_fraction(primary_mass) {
+        return (stars, orbits);
+    }
+
+    // Inner pair: flat mass ratios, log-normal periods peaking near 300 years
+    let secondary_mass = (primary_mass * rng.gen_range(0.1..1.0)).max(0.01);
+    let secondary = companion_star(path, 1, &stars[0], secondary_mass, &mut rng);
+    let pair_mass = primary_mass + secondary.physical.mass / 1.989e30;
+
+    let log_period_days = Normal::new(5.03f64, 2.28).unwrap().sample(&mut rng).clamp(0.0, 10.0);
+    let period_days = 10f64.powf(log_period_days);
+    // Tides circularise the closest binaries
+    let eccentricity = if period_days < 12.0 { 0.0 } else { rng.gen_range(0.0..0.8) };
+
+    // Keep the stars from touching at periapsis
+    let contact = 3.0 * (stars[0].physical.radius + secondary.physical.radius) / 1.496e11;
+    let separation = (pair_mass * (period_days / 365.25).powi(2))
+        .cbrt()
+        .max(contact / (1.0 - eccentricity));
+
+    orbits.push(companion_orbit(&mut rng, separation, eccentricity, pair_mass));
+    stars.push(secondary);
+
+    // A quarter of multiples have a distant third star
+    if rng.gen::<f64>() < 0.25 {
+        let tertiary_mass = (primary_mass * rng.gen_range(0.1..1.0)).max(0.01);
+        let tertiary = companion_star(path, 2, &stars[0], tertiary_mass, &mut rng);
+        let tertiary_mass = tertiary.physical.mass / 1.989e30;
+        let e_out = rng.gen_range(0.0..0.6);
+        let min_periapsis = separation * (1.0 + eccentricity)
+            * triple_stability_ratio(tertiary_mass / pair_mass, e_out);
+        let separation_out = min_periapsis / (1.0 - e_out) * rng.gen_range(1.2..10.0);
+
+        orbits.push(companion_orbit(&mut rng, separation_out, e_out, pair_mass + tertiary_mass));
+        stars.push(tertiary);
+    }
+
+    (stars, orbits)
+}
+
+/// Region of a multiple system where planets can orbit stably
+pub(crate) struct PlanetZone {
+    pub host: PlanetHost,
+    pub mass: f64,   // in solar masses, of the star or stars orbited
+    pub initial_mass: f64,      // in solar masses, before any mass loss
+    pub engulfing_radius: f64,  // in AU, largest radius the host has swelled to
+    pub inner: f64,  // in AU, innermost stable semi-major axis
+    pub outer: f64,  // in AU, outermost stable semi-major axis
+}
+
+/// Stable planet zones of a system. Binaries wide enough for the primary to hold
+/// planets beyond `min_orbit` AU get S-type zones around each component;
+/// closer pairs get a single circumbinary P-type zone.
+pub(crate) fn planet_zones(stars: &[Star], orbits: &[Orbit], min_orbit: f64) -> Vec<PlanetZone> {
+    let mass = |i: usize| stars[i].physical.mass / 1.989e30;
+    let around_star = |i: usize, outer: f64| PlanetZone {
+        host: PlanetHost::Star(i),
+        mass: mass(i),
+        initial_mass: stars[i].initial_mass,
+        engulfing_radius: stars[i].maximum_radius(),
+        inner: 0.0,
+        outer,
+    };
+
+    let mut zones = match orbits.first() {
+        None => vec![around_star(0, f64::INFINITY)],
+        Some(binary) => {
+            let (a, e) = (binary.semi_major_axis, binary.eccentricity);
+            let mu = mass(1) / (mass(0) + mass(1));
+            let primary_limit = s_type_stability_limit(a, e, mu);
+
+            if primary_limit > 2.0 * min_orbit {
+                vec![around_star(0, primary_limit), around_star(1, s_type_stability_limit(a, e, 1.0 - mu))]
+            } else {
+                vec![PlanetZone {
+                    host: PlanetHost::InnerBinary,
+                    mass: mass(0) + mass(1),
+                    initial_mass: stars[0].initial_mass + stars[1].initial_mass,
+                    engulfing_radius: stars[0].maximum_radius().max(stars[1].maximum_radius()),
+                    inner: p_type_stability_limit(a, e, mu),
+                    outer: f64::INFINITY,
+                }]
+            }
+        }
+    };
+
+    // A distant third star truncates everything inside it
+    if let Some(outer) = orbits.get(1) {
+        let inner_mass = mass(0) + mass(1);
+        let limit = s_type_stability_limit(
+            outer.semi_major_axis, outer.eccentricity, mass(2) / (inner_mass + mass(2)),
+        );
+        for zone in &mut zones {
+            zone.outer = zone.outer.min(limit);
+        }
+    }
+
+    zones
+}
+
+/// Luminosity (solar) of a lone star that would give a planet `distance` AU from
+/// `host` the flux it receives from every star in the system. Stars the planet
+/// does not orbit are taken to be at their mean separation from the host.
+pub(crate) fn effective_luminosity(stars: &[Star], orbits: &[Orbit], host: PlanetHost, distance: f64) -> f64 {
+    effective_output(stars, orbits, host, distance, |star| star.luminosity)
+}
+
+/// Ultraviolet counterpart of `effective_luminosity`, relative to the Sun's
+pub(crate) fn effective_ultraviolet(stars: &[Star], orbits: &[Orbit], host: PlanetHost, distance: f64) -> f64 {
+    effective_output(stars, orbits, host, distance, Star::ultraviolet_luminosity)
+}
+
+fn effective_output(
+    stars: &[Star],
+    orbits: &[Orbit],
+    host: PlanetHost,
+    distance: f64,
+    output: impl Fn(&Star) -> f64,
+) -> f64 {
+    stars.iter().enumerate().map(|(i, star)| {
+        let orbited = match host {
+            PlanetHost::Star(h) => i == h,
+            PlanetHost::InnerBinary => i < 2,
+        };
+        if orbited {
+            output(star)
+        } else {
+            let separation = orbits[i.max(1) - 1].semi_major_axis;
+            output(star) * (distance / separation).powi(2)
+        }
+    }).sum()
+}
+
+/// A companion born alongside `primary`, sharing its age and composition, and
+/// evolved to that age
+fn companion_star<R: Rng + ?Sized>(
+    path: &SeedPath,
+    index: u32,
+    primary: &Star,
+    mass: f64,
+    rng: &mut R,
+) -> Star {
+    let seed = path.star(index).seed();
+    let mut star_rng = StdRng::seed_from_u64(rng.gen());
+    Star::from_initial_mass(seed, &mut star_rng, mass, primary.age, primary.metallicity)
+}
+
+/// Relative orbit of a companion, roughly aligned with the system plane
+fn companion_orbit<R: Rng + ?Sized>(rng: &mut R, separation: f64, eccentricity: f64, total_mass: f64) -> Orbit {
+    Orbit::new(
+        separation,
+        eccentricity,
+        rng.gen_range(0.0..10f64.to_radians()),
+        rng.gen_range(0.0..2.0 * PI),
+        rng.gen_range(0.0..2.0 * PI),
+        rng.gen_range(0.0..2.0 * PI),
+        total_mass,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generate, SolarSystem};
+    use crate::tests::first_system;
+
+    fn multiple_systems() -> impl Iterator<Item = SolarSystem> {
+        (0..).map(SolarSystem::generate_with_seed).filter(|s| s.stars.len() > 1)
+    }
+
+    #[test]
+    fn test_stability_limits() {
+        // Equal-mass circular binary (Holman & Wiegert 1999, table values)
+        assert!((s_type_stability_limit(1.0, 0.0, 0.5) - 0.274).abs() < 1e-3);
+        assert!((p_type_stability_limit(1.0, 0.0, 0.5) - 2.3875).abs() < 1e-3);
+        // Eccentric binaries shrink the S-type zone and push P-type planets out
+        assert!(s_type_stability_limit(1.0, 0.5, 0.3) < s_type_stability_limit(1.0, 0.0, 0.3));
+        assert!(p_type_stability_limit(1.0, 0.5, 0.3) > p_type_stability_limit(1.0, 0.0, 0.3));
+    }
+
+    #[test]
+    fn test_multiplicity_rate() {
+        let multiples = (0..2000)
+            .filter(|&seed| SolarSystem::generate_with_seed(seed).stars.len() > 1)
+            .count();
+        let fraction = multiples as f64 / 2000.0;
+        assert!(fraction > 0.25 && fraction < 0.5, "multiplicity {:.2}", fraction);
+    }
+
+    #[test]
+    fn test_planets_respect_stability_limits() {
+        for system in multiple_systems().take(200) {
+            let binary = &system.companion_orbits[0];
+            let mass = |i: usize| system.stars[i].physical.mass;
+            let mu = mass(1) / (mass(0) + mass(1));
+
+            for planet in &system.planets {
+                let a = planet.orbit.semi_major_axis;
+                match planet.host {
+                    PlanetHost::Star(0) => assert!(planet.orbit.apoapsis()
+                        <= s_type_stability_limit(binary.semi_major_axis, binary.eccentricity, mu)),
+                    PlanetHost::Star(_) => assert!(planet.orbit.apoapsis()
+                        <= s_type_stability_limit(binary.semi_major_axis, binary.eccentricity, 1.0 - mu)),
+                    PlanetHost::InnerBinary => assert!(planet.orbit.periapsis()
+                        >= p_type_stability_limit(binary.semi_major_axis, binary.eccentricity, mu), "a = {}", a),
+                }
+            }
+
+            // Triples are hierarchical
+            if let Some(outer) = system.companion_orbits.get(1) {
+                assert!(outer.periapsis() > 2.8 * binary.apoapsis());
+            }
+        }
+    }
+
+    #[test]
+    fn test_both_configurations_occur() {
+        let systems: Vec<SolarSystem> = multiple_systems().take(300).collect();
+        let has = |host: fn(&PlanetHost) -> bool| {
+            systems.iter().any(|s| s.planets.iter().any(|p| host(&p.host)))
+        };
+        assert!(has(|h| *h == PlanetHost::InnerBinary));
+        assert!(has(|h| *h == PlanetHost::Star(0)));
+        assert!(has(|h| *h == PlanetHost::Star(1)));
+    }
+
+    #[test]
+    fn test_circumbinary_habitable_zone_uses_both_stars() {
+        let system = first_system("a circumbinary planet", |s| s.planets.iter().any(|p| p.host == PlanetHost::InnerBinary));
+        let primary_only = crate::distributions::habitable_zone_range(
+            system.star().physical.mass / 1.989e30,
+            system.star().luminosity,
+        );
+        assert!(system.habitable_zone.0 > primary_only.0);
+        assert!(system.habitable_zone.1 > primary_only.1);
+    }
+
+    #[test]
+    fn test_companion_orbits_follow_kepler() {
+        for system in multiple_systems().take(50) {
+            let mut inner_mass = system.stars[0].physical.mass / 1.989e30;
+            for (k, orbit) in system.companion_orbits.iter().enumerate() {
+                let total = inner_mass + system.stars[k + 1].physical.mass / 1.989e30;
+                assert!((orbit.period / Orbit::kepler_period(orbit.semi_major_axis, total) - 1.0).abs() < 1e-9);
+                inner_mass = total;
+            }
+            assert_eq!(system.stars[1].age, system.stars[0].age);
+        }
+    }
+}
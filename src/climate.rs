@@ -0,0 +1,369 @@
+//! Coarse climate and biome maps for solid planets.
+//!
+//! Annual-mean temperatures follow insolation by latitude, smoothed by heat
+//! carried in the atmosphere and cooled with altitude. Rainfall follows the
+//! circulation cells: wet where air rises at the equator and polar fronts,
+//! dry where it sinks, and drier again downwind of continents and mountains.
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use crate::surface::{grid_latitude, grid_longitude, Surface};
+use crate::{Planet, PlanetType};
+
+/// Rainfall (mm/year) under rising, saturated air at 288 K
+const RAINFALL_SCALE: f64 = 2000.0;
+
+/// Latitude (degrees) where an Earth-like day's Hadley circulation ends
+const EARTH_HADLEY_EXTENT: f64 = 30.0;
+
+/// Mean lapse rate (K/m) of the Earth's lower atmosphere
+const EARTH_LAPSE_RATE: f64 = 0.0065;
+
+/// Distance upwind (degrees of longitude) and step for tracing moisture
+const MOISTURE_FETCH: f64 = 40.0;
+const MOISTURE_STEP: f64 = 4.0;
+
+/// Annual-mean temperatures (K) below which ice sheets build on land and pack
+/// ice lasts through the summer at sea
+const LAND_ICE_TEMPERATURE: f64 = 263.0;
+const SEA_ICE_TEMPERATURE: f64 = 268.0;
+
+/// Whittaker-style biome of a patch of surface
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Ocean,
+    SeaIce,
+    IceSheet,
+    Tundra,
+    Taiga,                   // boreal conifer forest
+    TemperateForest,
+    TemperateRainforest,
+    Grassland,
+    Savanna,
+    TropicalSeasonalForest,
+    Rainforest,
+    Desert,
+    Barren,                  // lifeless rock, dust or regolith
+    Lava,                    // molten surface
+}
+
+impl Biome {
+    /// Whether the biome is defined by its plant life
+    pub fn is_vegetated(&self) -> bool {
+        matches!(
+            self,
+            Biome::Tundra | Biome::Taiga | Biome::TemperateForest | Biome::TemperateRainforest
+                | Biome::Grassland | Biome::Savanna | Biome::TropicalSeasonalForest | Biome::Rainforest
+        )
+    }
+}
+
+/// Equirectangular grid of biomes, laid out like `Heightmap`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BiomeMap {
+    pub width: usize,
+    pub height: usize,
+    pub biomes: Vec<Biome>,
+}
+
+impl BiomeMap {
+    pub fn get(&self, x: usize, y: usize) -> Biome {
+        self.biomes[y * self.width + x]
+    }
+
+    /// Latitude (degrees) of the centre of row `y`
+    pub fn latitude(&self, y: usize) -> f64 {
+        grid_latitude(y, self.height)
+    }
+
+    /// Longitude (degrees, -180 to 180) of the centre of column `x`
+    pub fn longitude(&self, x: usize) -> f64 {
+        grid_longitude(x, self.width)
+    }
+
+    /// Fraction of the map's area covered by `biome`, weighting rows by the
+    /// area they span on the sphere
+    pub fn coverage(&self, biome: Biome) -> f64 {
+        let (mut covered, mut total) = (0.0, 0.0);
+        for y in 0..self.height {
+            let weight = self.latitude(y).to_radians().cos();
+            for x in 0..self.width {
+                total += weight;
+                if self.get(x, y) == biome {
+                    covered += weight;
+                }
+            }
+        }
+        covered / total
+    }
+}
+
+/// Annual-mean insolation at `latitude` degrees relative to the planet's
+/// average, for an axial tilt in degrees (second-order Legendre fit of
+/// Ward 1974; North et al. 1981). Upright planets heat the equator; planets
+/// tilted past ~54° heat their poles more.
+pub fn insolation(latitude: f64, axial_tilt: f64) -> f64 {
+    let p2 = |x: f64| (3.0 * x * x - 1.0) / 2.0;
+    let s2 = -5.0 / 8.0 * p2(axial_tilt.to_radians().cos());
+    1.0 + s2 * p2(latitude.to_radians().sin())
+}
+
+/// Climate of a solid planet over its terrain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Climate {
+    pub surface: Surface,
+    pub mean_temperature: f64,  // in K, averaged over the surface
+    pub axial_tilt: f64,        // in degrees
+    pub heat_transport: f64,    // 0 (each latitude in radiative balance) to 1 (uniform temperature)
+    pub hadley_extent: f64,     // in degrees of latitude, poleward edge of the tropical circulation
+    pub lapse_rate: f64,        // in K/m, cooling with height above sea level
+    pub vegetated: bool,        // whether life covers land suited to it
+    pub molten: bool,           // surface is a magma ocean
+}
+
+impl Climate {
+    /// Height (m) above sea level, or above the datum on dry worlds
+    fn elevation(&self, latitude: f64, longitude: f64) -> f64 {
+        self.surface.height(latitude, longitude) - self.surface.sea_level.unwrap_or(0.0)
+    }
+
+    /// Annual-mean temperature (K) at sea level at `latitude` degrees
+    pub fn zonal_temperature(&self, latitude: f64) -> f64 {
+        let radiative = self.mean_temperature * insolation(latitude, self.axial_tilt).max(0.0).powf(0.25);
+        self.mean_temperature + (1.0 - self.heat_transport) * (radiative - self.mean_temperature)
+    }
+
+    /// Annual-mean surface temperature (K) at a latitude and longitude in degrees
+    pub fn temperature(&self, latitude: f64, longitude: f64) -> f64 {
+        let elevation = self.elevation(latitude, longitude).max(0.0);
+        self.zonal_temperature(latitude) - self.lapse_rate * elevation
+    }
+
+    /// Prevailing surface wind at `latitude`: +1 for westerlies blowing east,
+    /// -1 for easterlies blowing west
+    pub fn prevailing_wind(&self, latitude: f64) -> f64 {
+        // Trade winds in the tropical cell, westerlies in the next, and polar
+        // easterlies beyond; the pole belongs to the last cell
+        let last_cell = (90.0 / self.hadley_extent).ceil() as u32 - 1;
+        let cell = ((latitude.abs() / self.hadley_extent) as u32).min(last_cell);
+        if cell % 2 == 1 { 1.0 } else { -1.0 }
+    }
+
+    /// Moisture (0 to 1) of air arriving at a point, picked up over upwind
+    /// seas and wrung out over land and mountains
+    fn moisture(&self, latitude: f64, longitude: f64) -> f64 {
+        let Some(sea_level) = self.surface.sea_level else {
+            return 0.0;
+        };
+        let upwind = -self.prevailing_wind(latitude);
+        let mut moisture: f64 = 0.3;
+        let mut offset = MOISTURE_FETCH;
+        while offset >= 0.0 {
+            let height = self.surface.height(latitude, longitude + upwind * offset) - sea_level;
+            if height < 0.0 {
+                moisture += 0.3 * (1.0 - moisture);
+            } else {
+                moisture *= 0.95;
+                // Air forced over high ground rains out on the windward side
+                if height > 2000.0 && offset > 0.0 {
+                    moisture *= 0.5;
+                }
+            }
+            offset -= MOISTURE_STEP;
+        }
+        moisture
+    }
+
+    /// Annual rainfall (mm) at a latitude and longitude in degrees
+    pub fn precipitation(&self, latitude: f64, longitude: f64) -> f64 {
+        if self.molten {
+            return 0.0;
+        }
+        // Rising air at the equator and the polar front, sinking air at the
+        // subtropical highs and the poles
+        let cells = latitude.abs() / self.hadley_extent;
+        let circulation = 0.05 + 0.95 * (0.5 + 0.5 * (PI * cells).cos()).sqrt();
+        // Warm air holds more water, ~7% more per kelvin
+        let temperature = self.temperature(latitude, longitude);
+        let capacity = (0.07 * (temperature - 288.0)).exp().min(3.0);
+        RAINFALL_SCALE * circulation * capacity * self.moisture(latitude, longitude)
+    }
+
+    /// Whether ice covers a latitude and longitude, on land or at sea
+    pub fn is_ice(&self, latitude: f64, longitude: f64) -> bool {
+        matches!(self.biome(latitude, longitude), Biome::IceSheet | Biome::SeaIce)
+    }
+
+    /// Biome at a latitude and longitude in degrees
+    pub fn biome(&self, latitude: f64, longitude: f64) -> Biome {
+        if self.molten {
+            return Biome::Lava;
+        }
+        let temperature = self.temperature(latitude, longitude);
+        if self.surface.is_underwater(latitude, longitude) {
+            return if temperature < SEA_ICE_TEMPERATURE { Biome::SeaIce } else { Biome::Ocean };
+        }
+        // Ice sheets need snowfall to build them
+        let wet = self.surface.sea_level.is_some();
+        if temperature < LAND_ICE_TEMPERATURE {
+            return if wet { Biome::IceSheet } else { Biome::Barren };
+        }
+
+        let rainfall = self.precipitation(latitude, longitude);
+        if !self.vegetated || temperature > 330.0 {
+            return if rainfall < 250.0 { Biome::Desert } else { Biome::Barren };
+        }
+        let celsius = temperature - 273.15;
+        match (celsius, rainfall) {
+            (t, _) if t < -5.0 => Biome::Tundra,
+            (_, r) if r < 250.0 => Biome::Desert,
+            (t, _) if t < 5.0 => Biome::Taiga,
+            (t, r) if t < 20.0 => match r {
+                r if r < 500.0 => Biome::Grassland,
+                r if r < 2000.0 => Biome::TemperateForest,
+                _ => Biome::TemperateRainforest,
+            },
+            (_, r) if r < 1000.0 => Biome::Savanna,
+            (_, r) if r < 2000.0 => Biome::TropicalSeasonalForest,
+            _ => Biome::Rainforest,
+        }
+    }
+
+    /// Biomes on an equirectangular grid of `width` by `height` samples
+    pub fn biome_map(&self, width: usize, height: usize) -> BiomeMap {
+        let mut biomes = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                biomes.push(self.biome(grid_latitude(y, height), grid_longitude(x, width)));
+            }
+        }
+        BiomeMap { width, height, biomes }
+    }
+}
+
+impl Planet {
+    /// Climate over the terrain of a solid planet of `age` billion years
+    /// (see `Planet::surface`); giants have none. Only habitable worlds grow
+    /// vegetation.
+    pub fn climate(&self, age: f64) -> Option<Climate> {
+        let surface = self.surface(age)?;
+        let pressure = self.atmosphere.as_ref().map(|atm| atm.pressure).unwrap_or(0.0);
+
+        // Thick atmospheres even out temperatures (Venus is uniform); thin
+        // ones leave each latitude to its own sunlight. The Earth's air and
+        // oceans erase ~40% of the radiative equator-to-pole contrast
+        let heat_transport = pressure / (pressure + 1.5);
+        // Slow rotators stretch the tropical cells towards the poles (Titan, Venus)
+        let hadley_extent = (EARTH_HADLEY_EXTENT * self.rotation_period.max(0.01).cbrt()).min(90.0);
+        let lapse_rate = if pressure > 0.0 {
+            EARTH_LAPSE_RATE * self.physical.surface_gravity / 9.81
+        } else {
+            0.0
+        };
+
+        Some(Climate {
+            surface,
+            mean_temperature: self.physical.surface_temperature,
+            axial_tilt: self.axial_tilt,
+            heat_transport,
+            hadley_extent,
+            lapse_rate,
+            vegetated: self.habitable,
+            molten: self.planet_type == PlanetType::LavaWorld,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::earth_analogue;
+
+    /// An Earth analogue with the Earth's mean temperature and its life,
+    /// whatever its settled climate gives
+    fn earth() -> Planet {
+        let mut planet = earth_analogue(1.0, 1.0);
+        planet.physical.surface_temperature = 288.0;
+        planet.habitable = true;
+        planet
+    }
+
+    #[test]
+    fn test_insolation() {
+        // The Earth's poles get ~40% of the equator's annual sunlight
+        let ratio = insolation(90.0, 23.44) / insolation(0.0, 23.44);
+        assert!((ratio - 0.42).abs() < 0.05, "{}", ratio);
+        // Insolation averages to one over the sphere
+        let mean: f64 = (0..900).map(|i| {
+            let latitude = -90.0 + (i as f64 + 0.5) * 0.2;
+            insolation(latitude, 23.44) * latitude.to_radians().cos()
+        }).sum::<f64>() * 0.2f64.to_radians() / 2.0;
+        assert!((mean - 1.0).abs() < 1e-3, "{}", mean);
+        // Planets on their side have warm poles
+        assert!(insolation(90.0, 90.0) > insolation(0.0, 90.0));
+    }
+
+    #[test]
+    fn test_earth_climate() {
+        let climate = earth().climate(4.6).unwrap();
+
+        let equator = climate.zonal_temperature(0.0);
+        let pole = climate.zonal_temperature(90.0);
+        assert!(equator > 295.0 && equator < 310.0, "{}", equator);
+        assert!(pole < 270.0 && pole > 230.0, "{}", pole);
+
+        // Trade winds, westerlies, polar easterlies
+        assert_eq!(climate.prevailing_wind(10.0), -1.0);
+        assert_eq!(climate.prevailing_wind(-45.0), 1.0);
+        assert_eq!(climate.prevailing_wind(75.0), -1.0);
+
+        let map = climate.biome_map(96, 48);
+        let ocean = map.coverage(Biome::Ocean) + map.coverage(Biome::SeaIce);
+        assert!(ocean > 0.4 && ocean < 0.9, "{}", ocean);
+        let vegetated: f64 = map.biomes.iter().filter(|b| b.is_vegetated()).count() as f64 / map.biomes.len() as f64;
+        assert!(vegetated > 0.05, "{}", vegetated);
+        // Ice gathers at the poles, not the equator
+        assert!((0..map.width).any(|x| climate.is_ice(map.latitude(0), map.longitude(x))));
+        assert!((0..map.width).all(|x| !climate.is_ice(0.0, map.longitude(x))));
+    }
+
+    #[test]
+    fn test_climate_extremes() {
+        // A dense atmosphere spreads heat evenly
+        let mut venus = earth();
+        venus.atmosphere.as_mut().unwrap().pressure = 90.0;
+        let climate = venus.climate(4.6).unwrap();
+        assert!((climate.zonal_temperature(0.0) - climate.zonal_temperature(80.0)).abs() < 2.0);
+
+        // Lifeless worlds have rock, dust, water and ice but no forests
+        let mut barren = earth();
+        barren.habitable = false;
+        let map = barren.climate(4.6).unwrap().biome_map(48, 24);
+        assert!(map.biomes.iter().all(|b| !b.is_vegetated()));
+
+        // Dry worlds have no seas and little rain
+        let mut dry = earth();
+        dry.water_fraction = 0.0;
+        let climate = dry.climate(4.6).unwrap();
+        assert_eq!(climate.precipitation(0.0, 0.0), 0.0);
+        assert_eq!(climate.biome_map(24, 12).coverage(Biome::Ocean), 0.0);
+
+        // Slow rotators have one broad cell per hemisphere
+        let mut slow = earth();
+        slow.rotation_period = 30.0;
+        let climate = slow.climate(4.6).unwrap();
+        assert!(climate.hadley_extent > 80.0);
+        assert_eq!(climate.prevailing_wind(90.0), -1.0);
+        assert_eq!(climate.prevailing_wind(-90.0), -1.0);
+        // So do planets locked to a red dwarf, however fast they were born spinning
+        let mut locked = earth();
+        locked.orbit = crate::Orbit::new(0.07, 0.0, 0.0, 0.0, 0.0, 0.0, 0.2);
+        locked.despin(0.2, 4.6);
+        assert!(locked.tidally_locked && locked.rotation_period == locked.orbital_period() * 365.25);
+        let hadley_extent = locked.climate(4.6).unwrap().hadley_extent;
+        assert!(hadley_extent > 2.0 * EARTH_HADLEY_EXTENT, "{}", hadley_extent);
+
+        let giant = Planet::generate_at_distance(999999, 5.0);
+        assert!(giant.climate(4.6).is_none());
+    }
+}
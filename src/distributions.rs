@@ -1,25 +1,94 @@
 use lazy_static::lazy_static;
 use rand::Rng;
 use rand_distr::{Distribution, LogNormal, Normal};
+use serde::{Deserialize, Serialize};
 
 lazy_static! {
-    // Star mass distribution (in solar masses)
-    static ref STAR_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(0.0, 0.5).unwrap();
-    
     // Planet mass distributions for different types (in Earth masses)
     static ref TERRESTRIAL_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(-0.5, 0.5).unwrap();
+    static ref SUPER_EARTH_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(1.4, 0.4).unwrap();
+    static ref DWARF_PLANET_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(-6.5, 1.0).unwrap();
     static ref ICE_GIANT_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(2.5, 0.3).unwrap();
     static ref GAS_GIANT_MASS_DISTRIBUTION: LogNormal<f64> = LogNormal::new(5.0, 0.4).unwrap();
     
-    // Orbital period distribution (in Earth years)
-    static ref ORBITAL_PERIOD_DISTRIBUTION: LogNormal<f64> = LogNormal::new(0.5, 1.0).unwrap();
-    
     // Metallicity distribution (centered around solar metallicity)
     static ref METALLICITY_DISTRIBUTION: Normal<f64> = Normal::new(0.0, 0.2).unwrap();
 }
 
+const SOLAR_LUMINOSITY: f64 = 3.828e26;   // W (IAU nominal)
+const SOLAR_RADIUS: f64 = 6.957e8;        // m
+const STEFAN_BOLTZMANN: f64 = 5.670374e-8; // W/m²/K⁴
+
+const MIN_STAR_MASS: f64 = 0.01;   // solar masses, the deuterium-burning limit
+const MAX_STAR_MASS: f64 = 150.0;  // solar masses
+
+/// Initial mass function: the distribution of stellar masses at birth
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum InitialMassFunction {
+    Kroupa,    // Broken power law, dN/dM ∝ M^-0.3, M^-1.3, M^-2.3 (Kroupa 2001)
+    Chabrier,  // Log-normal below 1 M☉ with a Salpeter-like tail (Chabrier 2003)
+}
+
+impl InitialMassFunction {
+    /// Draw an initial stellar mass in solar masses, 0.01 to 150
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self {
+            InitialMassFunction::Kroupa => {
+                // Segments joined continuously at 0.08 and 0.5 M☉
+                let segments = [
+                    (MIN_STAR_MASS, 0.08, 0.3, 1.0),
+                    (0.08, 0.5, 1.3, 0.08),
+                    (0.5, MAX_STAR_MASS, 2.3, 0.08 * 0.5),
+                ];
+                let weights = segments.map(|(a, b, alpha, k)| power_law_integral(a, b, alpha) * k);
+                let mut roll = rng.gen::<f64>() * weights.iter().sum::<f64>();
+                for ((a, b, alpha, _), weight) in segments.into_iter().zip(weights) {
+                    if roll < weight {
+                        return sample_power_law(rng, a, b, alpha);
+                    }
+                    roll -= weight;
+                }
+                sample_power_law(rng, 0.5, MAX_STAR_MASS, 2.3)
+            }
+            InitialMassFunction::Chabrier => {
+                // dN/dlog M; rejection sampling in log mass against the peak
+                let density = |log_mass: f64| {
+                    let gaussian = |x: f64| (-(x - 0.079f64.log10()).powi(2) / (2.0 * 0.69 * 0.69)).exp();
+                    if log_mass <= 0.0 { gaussian(log_mass) } else { gaussian(0.0) * 10f64.powf(-1.3 * log_mass) }
+                };
+                loop {
+                    let log_mass = rng.gen_range(MIN_STAR_MASS.log10()..MAX_STAR_MASS.log10());
+                    if rng.gen::<f64>() < density(log_mass) {
+                        return 10f64.powf(log_mass);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// ∫ M^-alpha dM from a to b
+fn power_law_integral(a: f64, b: f64, alpha: f64) -> f64 {
+    (b.powf(1.0 - alpha) - a.powf(1.0 - alpha)) / (1.0 - alpha)
+}
+
+/// Inverse-transform sample of dN/dM ∝ M^-alpha between a and b
+fn sample_power_law<R: Rng + ?Sized>(rng: &mut R, a: f64, b: f64, alpha: f64) -> f64 {
+    let exponent = 1.0 - alpha;
+    (a.powf(exponent) + rng.gen::<f64>() * (b.powf(exponent) - a.powf(exponent))).powf(1.0 / exponent)
+}
+
 /// Generate a random planet mass in Earth masses based on desired type
 pub fn random_planet_mass<R: Rng + ?Sized>(rng: &mut R, distance_from_star: f64) -> f64 {
+    random_planet_mass_with_metallicity(rng, distance_from_star, 0.0)
+}
+
+/// Generate a random planet mass in Earth masses around a star of the given [Fe/H]
+pub fn random_planet_mass_with_metallicity<R: Rng + ?Sized>(
+    rng: &mut R,
+    distance_from_star: f64,
+    metallicity: f64,
+) -> f64 {
     // Probability of different planet types varies with distance
     // Based on Kepler data and exoplanet observations
     let terrestrial_prob = match distance_from_star {
@@ -38,15 +107,28 @@ pub fn random_planet_mass<R: Rng + ?Sized>(rng: &mut R, distance_from_star: f64)
     
     // This makes gas giants very common in outer system since
     // gas_giant_prob = 1.0 - (terrestrial_prob + ice_giant_prob)
-    
-    let roll = rng.gen::<f64>();
+    let gas_giant_prob = (1.0 - terrestrial_prob - ice_giant_prob)
+        * giant_planet_metallicity_factor(metallicity);
+
+    // Renormalise so metal-poor disks shift their mass budget to small planets
+    let total = terrestrial_prob + ice_giant_prob + gas_giant_prob;
+    let roll = rng.gen::<f64>() * total;
     
     if roll < terrestrial_prob {
-        // Terrestrial planet (0.1-2 Earth masses)
-        TERRESTRIAL_MASS_DISTRIBUTION.sample(rng).max(0.1).min(2.0)
+        // Small planets: dwarf planets beyond the asteroid belt (Pluto is
+        // 0.002 Earth masses), super-Earths and mini-Neptunes (2-10 Earth
+        // masses), or Earth-sized worlds (0.1-2 Earth masses)
+        let size = rng.gen::<f64>();
+        if distance_from_star > 2.0 && size < 0.3 {
+            DWARF_PLANET_MASS_DISTRIBUTION.sample(rng).clamp(1e-4, 0.1)
+        } else if size > 0.6 {
+            SUPER_EARTH_MASS_DISTRIBUTION.sample(rng).clamp(2.0, 10.0)
+        } else {
+            TERRESTRIAL_MASS_DISTRIBUTION.sample(rng).clamp(0.1, 2.0)
+        }
     } else if roll < (terrestrial_prob + ice_giant_prob) {
         // Ice giant (10-50 Earth masses)
-        ICE_GIANT_MASS_DISTRIBUTION.sample(rng).max(10.0).min(50.0)
+        ICE_GIANT_MASS_DISTRIBUTION.sample(rng).clamp(10.0, 50.0)
     } else {
         // Gas giant (50-1000 Earth masses)
         // Increase minimum mass for outer system gas giants
@@ -55,9 +137,88 @@ pub fn random_planet_mass<R: Rng + ?Sized>(rng: &mut R, distance_from_star: f64)
     }
 }
 
-/// Generate a random orbital period in Earth years
-pub fn random_orbital_period<R: Rng + ?Sized>(rng: &mut R) -> f64 {
-    ORBITAL_PERIOD_DISTRIBUTION.sample(rng)
+/// Relative gas giant occurrence for a host star of the given [Fe/H]
+///
+/// Giant planet occurrence scales as 10^(2[Fe/H]) (Fischer & Valenti 2005)
+pub fn giant_planet_metallicity_factor(metallicity: f64) -> f64 {
+    10f64.powf(2.0 * metallicity).clamp(0.01, 5.0)
+}
+
+/// Fraction of the usual planet count formed around a star of the given [Fe/H]
+pub fn planet_occurrence_factor(metallicity: f64) -> f64 {
+    10f64.powf(0.3 * metallicity).clamp(0.2, 1.0)
+}
+
+/// Main sequence lifetime in billions of years for a star of the given mass (solar masses)
+pub fn main_sequence_lifetime(star_mass: f64) -> f64 {
+    10.0 * star_mass.powf(-2.5)
+}
+
+/// Mass (solar masses) left behind by a star of the given initial mass once it
+/// has shed its envelope or exploded
+///
+/// White dwarfs follow the initial–final mass relation of Kalirai et al. (2008)
+pub fn remnant_mass(initial_mass: f64) -> f64 {
+    match initial_mass {
+        m if m < 8.0 => (0.109 * m + 0.394).min(1.38),
+        m if m < 25.0 => 1.4,
+        m => (0.3 * m).max(3.0),
+    }
+}
+
+/// Luminosity (solar) of a main sequence star of the given mass (solar masses)
+///
+/// Piecewise mass–luminosity relation (Duric 2004), made continuous at 2 M☉
+pub fn main_sequence_luminosity(star_mass: f64) -> f64 {
+    match star_mass {
+        m if m < 0.43 => 0.23 * m.powf(2.3),
+        m if m < 2.0 => m.powi(4),
+        m if m < 55.0 => 16.0 * (m / 2.0).powf(3.5),
+        m => 32000.0 * m,
+    }
+}
+
+/// Radius (solar radii) of a main sequence star of the given mass (solar masses)
+pub fn main_sequence_radius(star_mass: f64) -> f64 {
+    if star_mass <= 1.0 { star_mass.powf(0.8) } else { star_mass.powf(0.57) }
+}
+
+/// Luminosity (solar) of a brown dwarf of the given mass (solar masses) and age
+/// (Gyr); with no fusion to sustain them they fade as they cool (Burrows et al. 2001)
+pub fn brown_dwarf_luminosity(mass: f64, age: f64) -> f64 {
+    4e-5 * (mass / 0.05).powf(2.64) * age.max(0.01).powf(-1.3)
+}
+
+/// Effective temperature (K) of a body of the given luminosity (solar) and radius
+/// (solar radii), from L = 4πR²σT⁴
+pub fn effective_temperature(luminosity: f64, radius: f64) -> f64 {
+    let radius = radius * SOLAR_RADIUS;
+    (luminosity * SOLAR_LUMINOSITY / (4.0 * std::f64::consts::PI * radius * radius * STEFAN_BOLTZMANN))
+        .powf(0.25)
+}
+
+/// Luminosity (solar) of a body of the given radius (solar radii) and effective
+/// temperature (K), from L = 4πR²σT⁴
+pub fn blackbody_luminosity(radius: f64, temperature: f64) -> f64 {
+    let radius = radius * SOLAR_RADIUS;
+    4.0 * std::f64::consts::PI * radius * radius * STEFAN_BOLTZMANN * temperature.powi(4) / SOLAR_LUMINOSITY
+}
+
+/// Generate a random planetary orbital eccentricity
+///
+/// Multi-planet systems have near-circular orbits, with eccentricities
+/// following a Rayleigh distribution (Kepler multis: sigma ~ 0.05)
+pub fn random_planet_eccentricity<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    random_rayleigh(rng, 0.05).min(0.5)
+}
+
+/// Generate a random planetary orbital inclination in radians from the system plane
+pub fn random_planet_inclination<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    random_rayleigh(rng, 1.5f64.to_radians())
+}
+
+fn random_rayleigh<R: Rng + ?Sized>(rng: &mut R, sigma: f64) -> f64 {
+    sigma * (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt()
 }
 
 /// Generate a random metallicity value (relative to solar)
@@ -75,17 +236,44 @@ pub fn habitable_zone_range(star_mass: f64, luminosity: f64) -> (f64, f64) {
     (inner_bound, outer_bound)
 }
 
-/// Calculate surface temperature for a planet at given distance from star
-pub fn calculate_surface_temperature(distance_au: f64, stellar_luminosity: f64, greenhouse_effect: f64) -> f64 {
-    // Using the Stefan-Boltzmann law and inverse square law
-    // Base temperature for Earth-like albedo
-    let base_temp = 278.0 * (stellar_luminosity.powf(0.25) / distance_au.powf(0.5));
-    
-    // Apply greenhouse effect
-    let temp = base_temp * greenhouse_effect;
+/// Distance in AU beyond which water condenses to ice in a star's disk
+pub fn snow_line(luminosity: f64) -> f64 {
+    2.7 * luminosity.sqrt()
+}
+
+/// Equilibrium temperature (K) of a body absorbing starlight at `distance_au`,
+/// reflecting a `bond_albedo` fraction and reradiating evenly over its surface
+pub fn equilibrium_temperature(distance_au: f64, stellar_luminosity: f64, bond_albedo: f64) -> f64 {
+    // (L (1 - A) / (16 π σ d²))^¼, which is 278.6 K for a black body at 1 AU from the Sun
+    278.6 * (stellar_luminosity * (1.0 - bond_albedo)).powf(0.25) / distance_au.sqrt()
+}
+
+/// Infrared optical depth of an atmosphere with `greenhouse_pressure` bar of
+/// absorbing gases in `total_pressure` bar; the power law is fitted to Earth,
+/// Venus and Mars, with pressure broadening making thick atmospheres opaque
+pub fn greenhouse_optical_depth(greenhouse_pressure: f64, total_pressure: f64) -> f64 {
+    if greenhouse_pressure <= 0.0 || total_pressure <= 0.0 {
+        return 0.0;
+    }
+    5.3 * greenhouse_pressure.powf(0.4) * total_pressure.powf(0.32)
+}
+
+/// Calculate surface temperature for a planet at given distance from star,
+/// warmed by a grey atmosphere of the given infrared optical depth
+pub fn calculate_surface_temperature<R: Rng + ?Sized>(
+    rng: &mut R,
+    distance_au: f64,
+    stellar_luminosity: f64,
+    bond_albedo: f64,
+    optical_depth: f64,
+) -> f64 {
+    let base_temp = equilibrium_temperature(distance_au, stellar_luminosity, bond_albedo);
+
+    // Radiative equilibrium of a grey atmosphere (Eddington approximation)
+    let temp = base_temp * (1.0 + 0.75 * optical_depth).powf(0.25);
     
     // Add some random variation (±5%)
-    temp * (0.95 + rand::thread_rng().gen::<f64>() * 0.1)
+    temp * (0.95 + rng.gen::<f64>() * 0.1)
 }
 
 /// Calculate probability of planet having a moon
@@ -97,14 +285,14 @@ pub fn moon_probability(planet_mass: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::thread_rng;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn test_distributions_positive() {
-        let mut rng = thread_rng();
+        let mut rng = StdRng::seed_from_u64(1);
         for _ in 0..1000 {
             assert!(random_planet_mass(&mut rng, 1.0) > 0.0);
-            assert!(random_orbital_period(&mut rng) > 0.0);
         }
     }
 
@@ -123,17 +311,51 @@ mod tests {
 
     #[test]
     fn test_surface_temperature() {
-        // Test Earth-like conditions
-        let temp = calculate_surface_temperature(1.0, 1.0, 1.0);
-        assert!((temp - 278.0).abs() < 30.0); // Allow for random variation
+        let mut rng = StdRng::seed_from_u64(2);
+
+        // Earth without its greenhouse sits at its equilibrium temperature
+        assert!((equilibrium_temperature(1.0, 1.0, 0.306) - 254.6).abs() < 1.0);
+        let temp = calculate_surface_temperature(&mut rng, 1.0, 1.0, 0.306, 0.0);
+        assert!((temp - 254.6).abs() < 15.0); // Allow for random variation
         
         // Test temperature decreases with distance
-        let temp_far = calculate_surface_temperature(2.0, 1.0, 1.0);
+        let temp_far = calculate_surface_temperature(&mut rng, 2.0, 1.0, 0.306, 0.0);
         assert!(temp_far < temp);
         
         // Test temperature increases with luminosity
-        let temp_bright = calculate_surface_temperature(1.0, 2.0, 1.0);
+        let temp_bright = calculate_surface_temperature(&mut rng, 1.0, 2.0, 0.306, 0.0);
         assert!(temp_bright > temp);
+
+        // Earth's own greenhouse brings it to ~288 K
+        let tau = greenhouse_optical_depth(0.01, 1.0);
+        let earth = equilibrium_temperature(1.0, 1.0, 0.306) * (1.0 + 0.75 * tau).powf(0.25);
+        assert!((earth - 288.0).abs() < 5.0, "{}", earth);
+        assert_eq!(greenhouse_optical_depth(0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_initial_mass_functions() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for imf in [InitialMassFunction::Kroupa, InitialMassFunction::Chabrier] {
+            let masses: Vec<f64> = (0..20000).map(|_| imf.sample(&mut rng)).collect();
+            let fraction = |lo: f64, hi: f64| {
+                masses.iter().filter(|&&m| m >= lo && m < hi).count() as f64 / masses.len() as f64
+            };
+
+            assert!(masses.iter().all(|&m| (MIN_STAR_MASS..=MAX_STAR_MASS).contains(&m)));
+            // M dwarfs dominate by number, brown dwarfs are common, massive stars rare
+            assert!(fraction(0.08, 0.5) > 0.35, "{:?}", imf);
+            assert!(fraction(0.01, 0.08) > 0.1 && fraction(0.01, 0.08) < 0.5, "{:?}", imf);
+            assert!(fraction(0.8, 1.2) > 0.02 && fraction(0.8, 1.2) < 0.1, "{:?}", imf);
+            assert!(fraction(8.0, 150.0) < 0.01, "{:?}", imf);
+        }
+    }
+
+    #[test]
+    fn test_surface_temperature_uses_caller_rng() {
+        let temp = |seed| calculate_surface_temperature(&mut StdRng::seed_from_u64(seed), 1.0, 1.0, 0.3, 1.0);
+        assert_eq!(temp(42), temp(42));
+        assert_ne!(temp(42), temp(43));
     }
 
     #[test]
@@ -148,18 +370,18 @@ mod tests {
 
     #[test]
     fn test_planet_mass_distribution() {
-        let mut rng = thread_rng();
+        let mut rng = StdRng::seed_from_u64(3);
         let mut inner_terrestrial = 0;
         let mut outer_giants = 0;
         
         // Test inner system favors terrestrial planets
         for _ in 0..1000 {
             let mass = random_planet_mass(&mut rng, 0.3);
-            if mass < 2.0 {
+            if mass < 10.0 {
                 inner_terrestrial += 1;
             }
         }
-        assert!(inner_terrestrial > 500); // At least 50% terrestrial
+        assert!(inner_terrestrial > 500); // At least 50% super-Earths and smaller
         
         // Test outer system favors giants
         for _ in 0..1000 {
@@ -170,4 +392,25 @@ mod tests {
         }
         assert!(outer_giants > 700); // At least 70% giants
     }
+
+    #[test]
+    fn test_giant_planets_need_metals() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let count_giants = |rng: &mut StdRng, metallicity: f64| {
+            (0..2000)
+                .filter(|_| random_planet_mass_with_metallicity(rng, 3.0, metallicity) >= 50.0)
+                .count()
+        };
+
+        let metal_poor = count_giants(&mut rng, -1.5);
+        let solar = count_giants(&mut rng, 0.0);
+        let metal_rich = count_giants(&mut rng, 0.3);
+        assert!(metal_poor < solar && solar < metal_rich);
+    }
+
+    #[test]
+    fn test_stellar_lifetimes() {
+        assert!((main_sequence_lifetime(1.0) - 10.0).abs() < 1e-9);
+        assert!(main_sequence_lifetime(10.0) < 0.1);
+    }
 }
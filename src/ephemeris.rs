@@ -0,0 +1,241 @@
+//! Positions and velocities of a system's bodies at an arbitrary time.
+//!
+//! Times are in Earth years after the system's epoch, the instant at which
+//! generated `position` fields are given. States are relative to the
+//! barycentre of the system's stars; generated planet positions are relative
+//! to each planet's host.
+
+use serde::{Deserialize, Serialize};
+use crate::{PlanetHost, Position, SmallBody, SolarSystem, Velocity};
+
+/// Where a body is and how it is moving at a given time
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BodyState {
+    pub id: u64,
+    pub position: Position,  // in meters
+    pub velocity: Velocity,  // in m/s
+}
+
+/// Snapshot of a system's bodies at one time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemState {
+    pub time: f64,  // in Earth years after the epoch
+    pub stars: Vec<BodyState>,
+    pub planets: Vec<BodyState>,
+    pub moons: Vec<BodyState>,  // in planet order, each planet's moons in turn
+    pub small_bodies: Vec<BodyState>,  // those passed to `SolarSystem::state_at`, in order
+}
+
+impl SmallBody {
+    pub fn state_at(&self, time: f64) -> BodyState {
+        BodyState {
+            id: self.id,
+            position: self.orbit.position_at(time),
+            velocity: self.orbit.velocity_at(time),
+        }
+    }
+}
+
+fn add(a: Position, b: Position) -> Position {
+    Position { x: a.x + b.x, y: a.y + b.y, z: a.z + b.z }
+}
+
+fn add_velocity(a: Velocity, b: Velocity) -> Velocity {
+    Velocity { x: a.x + b.x, y: a.y + b.y, z: a.z + b.z }
+}
+
+impl SolarSystem {
+    /// State of every star `time` Earth years after the epoch. Each companion
+    /// and the stars it orbits swing about their common barycentre.
+    pub fn star_states_at(&self, time: f64) -> Vec<BodyState> {
+        let zero = BodyState {
+            id: self.stars[0].id,
+            position: Position { x: 0.0, y: 0.0, z: 0.0 },
+            velocity: Velocity { x: 0.0, y: 0.0, z: 0.0 },
+        };
+        let mut states = vec![zero];
+        let mut inner_mass = self.stars[0].physical.mass;
+
+        for (orbit, star) in self.companion_orbits.iter().zip(&self.stars[1..]) {
+            let offset = orbit.position_at(time);
+            let relative = orbit.velocity_at(time);
+            let mu = star.physical.mass / (inner_mass + star.physical.mass);
+
+            for state in &mut states {
+                state.position = add(state.position, Position { x: -mu * offset.x, y: -mu * offset.y, z: -mu * offset.z });
+                state.velocity = add_velocity(state.velocity, Velocity { x: -mu * relative.x, y: -mu * relative.y, z: -mu * relative.z });
+            }
+            let outer = 1.0 - mu;
+            states.push(BodyState {
+                id: star.id,
+                position: Position { x: outer * offset.x, y: outer * offset.y, z: outer * offset.z },
+                velocity: Velocity { x: outer * relative.x, y: outer * relative.y, z: outer * relative.z },
+            });
+            inner_mass += star.physical.mass;
+        }
+
+        states
+    }
+
+    /// State of every star, planet and moon `time` Earth years after the
+    /// epoch, and of the given small bodies, which are generated on demand
+    /// rather than stored with the system and orbit the primary
+    pub fn state_at(&self, time: f64, small_bodies: &[SmallBody]) -> SystemState {
+        let stars = self.star_states_at(time);
+        let mut planets = Vec::with_capacity(self.planets.len());
+        let mut moons = Vec::new();
+
+        for planet in &self.planets {
+            // Planet orbits are relative to their host star or binary
+            let (host_position, host_velocity) = match planet.host {
+                PlanetHost::Star(i) => (stars[i].position, stars[i].velocity),
+                PlanetHost::InnerBinary => {
+                    let (m0, m1) = (self.stars[0].physical.mass, self.stars[1].physical.mass);
+                    let weight = |a: f64, b: f64| (m0 * a + m1 * b) / (m0 + m1);
+                    let (s0, s1) = (&stars[0], &stars[1]);
+                    (
+                        Position {
+                            x: weight(s0.position.x, s1.position.x),
+                            y: weight(s0.position.y, s1.position.y),
+                            z: weight(s0.position.z, s1.position.z),
+                        },
+                        Velocity {
+                            x: weight(s0.velocity.x, s1.velocity.x),
+                            y: weight(s0.velocity.y, s1.velocity.y),
+                            z: weight(s0.velocity.z, s1.velocity.z),
+                        },
+                    )
+                }
+            };
+            let position = add(host_position, planet.orbit.position_at(time));
+            let velocity = add_velocity(host_velocity, planet.orbit.velocity_at(time));
+            planets.push(BodyState { id: planet.id, position, velocity });
+
+            // Moon orbits are relative to the planet
+            for moon in &planet.moons {
+                moons.push(BodyState {
+                    id: moon.id,
+                    position: add(position, moon.orbit.position_at(time)),
+                    velocity: add_velocity(velocity, moon.orbit.velocity_at(time)),
+                });
+            }
+        }
+
+        let primary = stars[0];
+        let small_bodies = small_bodies.iter().map(|body| {
+            let body = body.state_at(time);
+            BodyState {
+                id: body.id,
+                position: add(primary.position, body.position),
+                velocity: add_velocity(primary.velocity, body.velocity),
+            }
+        }).collect();
+
+        SystemState { time, stars, planets, moons, small_bodies }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SmallBodyGeneration;
+    use crate::tests::first_system;
+
+    fn system_with_planets() -> SolarSystem {
+        first_system("a single star with three planets", |system| system.stars.len() == 1 && system.planets.len() >= 3)
+    }
+
+    #[test]
+    fn test_epoch_matches_generated_positions() {
+        let system = system_with_planets();
+        let state = system.state_at(0.0, &[]);
+
+        assert_eq!(state.planets.len(), system.planets.len());
+        for (body, planet) in state.planets.iter().zip(&system.planets) {
+            assert_eq!(body.id, planet.id);
+            assert!((body.position.x - planet.position().x).abs() < 1.0);
+            assert!((body.position.y - planet.position().y).abs() < 1.0);
+            assert!((body.position.z - planet.position().z).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_planets_move_and_return() {
+        let system = system_with_planets();
+        let planet = &system.planets[0];
+
+        let quarter = system.state_at(planet.orbital_period() / 4.0, &[]);
+        let full = system.state_at(planet.orbital_period(), &[]);
+        let start = system.state_at(0.0, &[]);
+
+        let moved = (quarter.planets[0].position.x - start.planets[0].position.x).abs()
+            + (quarter.planets[0].position.y - start.planets[0].position.y).abs();
+        assert!(moved > 1e9);
+        assert!((full.planets[0].position.x - start.planets[0].position.x).abs() < 1e3);
+        assert!((full.planets[0].velocity.y - start.planets[0].velocity.y).abs() < 1e-3);
+
+        // Inner planets orbit faster
+        let speed = |b: &BodyState| (b.velocity.x.powi(2) + b.velocity.y.powi(2)).sqrt();
+        assert!(speed(&start.planets[0]) > speed(start.planets.last().unwrap()));
+    }
+
+    #[test]
+    fn test_moons_follow_their_planet() {
+        let system = first_system("a system with moons", |system| system.planets.iter().any(|p| !p.moons.is_empty()));
+        let state = system.state_at(1.7, &[]);
+
+        let total_moons: usize = system.planets.iter().map(|p| p.moons.len()).sum();
+        assert_eq!(state.moons.len(), total_moons);
+
+        let (index, planet) = system.planets.iter().enumerate().find(|(_, p)| !p.moons.is_empty()).unwrap();
+        let moon = &planet.moons[0];
+        let moon_state = state.moons.iter().find(|m| m.id == moon.id).unwrap();
+        let planet_state = &state.planets[index];
+
+        let separation = ((moon_state.position.x - planet_state.position.x).powi(2)
+            + (moon_state.position.y - planet_state.position.y).powi(2)
+            + (moon_state.position.z - planet_state.position.z).powi(2)).sqrt();
+        assert!(separation <= moon.orbit.apoapsis() * 1.496e11 * 1.000001);
+        assert!(separation >= moon.orbit.periapsis() * 1.496e11 * 0.999999);
+    }
+
+    #[test]
+    fn test_small_body_states() {
+        let system = system_with_planets();
+        let belt = Position { x: 2.7, y: 0.0, z: 0.0 };
+        let bodies = system.generate_small_bodies(belt, 0.5, 10.0);
+
+        let state = system.state_at(3.5, &bodies);
+        assert_eq!(state.small_bodies.len(), bodies.len());
+        assert_eq!(state.planets.len(), system.planets.len());
+
+        let again = system.state_at(3.5, &bodies);
+        assert_eq!(state.small_bodies[0].position.x, again.small_bodies[0].position.x);
+    }
+
+    #[test]
+    fn test_stars_orbit_barycentre() {
+        let system = first_system("a triple star with planets", |system| system.stars.len() == 3 && !system.planets.is_empty());
+
+        for time in [0.0, 13.7, 250.0] {
+            let state = system.state_at(time, &[]);
+            assert_eq!(state.stars.len(), 3);
+
+            // Momentum and mass-weighted position of the stars both vanish
+            let total: f64 = system.stars.iter().map(|s| s.physical.mass).sum();
+            let weighted = |f: fn(&BodyState) -> f64| {
+                system.stars.iter().zip(&state.stars).map(|(s, b)| s.physical.mass * f(b)).sum::<f64>() / total
+            };
+            let separation = system.companion_orbits[1].apoapsis() * 1.496e11;
+            assert!(weighted(|b| b.position.x).abs() < separation * 1e-9);
+            assert!(weighted(|b| b.velocity.y).abs() < 1e-6);
+
+            // The inner pair stays at its orbital separation
+            let inner = &system.companion_orbits[0];
+            let (a, b) = (&state.stars[0].position, &state.stars[1].position);
+            let distance = ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt();
+            assert!(distance <= inner.apoapsis() * 1.496e11 * 1.000001);
+            assert!(distance >= inner.periapsis() * 1.496e11 * 0.999999);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Stellar evolution: what a star born with a given mass and composition has
+//! become by a given age.
+//!
+//! Stars brighten slowly on the main sequence, swell into giants for roughly a
+//! tenth of their main sequence lifetime, then collapse to a remnant set by
+//! their initial mass. Planets around evolved hosts are swallowed by the giant
+//! envelope or drift outward as the star sheds mass.
+
+use serde::{Deserialize, Serialize};
+use crate::{Orbit, Planet, Star, StellarType};
+use crate::orbit::AU;
+use crate::distributions::{
+    blackbody_luminosity, brown_dwarf_luminosity, main_sequence_lifetime, main_sequence_luminosity,
+    main_sequence_radius, remnant_mass,
+};
+
+const GIANT_PHASE: f64 = 0.1;          // post-main-sequence lifetime, as a fraction of the main sequence
+const NEUTRON_STAR_RADIUS: f64 = 12e3; // m
+const SOLAR_RADIUS: f64 = 6.957e8;     // m
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EvolutionaryStage {
+    MainSequence,  // Burning hydrogen in the core; brown dwarfs never leave
+    GiantBranch,   // Shell burning, with a swollen envelope
+    Remnant,       // White dwarf, neutron star or black hole
+}
+
+/// A star's bulk properties at some age
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EvolvedState {
+    pub stage: EvolutionaryStage,
+    pub mass: f64,        // in solar masses, after mass loss
+    pub radius: f64,      // in solar radii
+    pub luminosity: f64,  // relative to Sol
+}
+
+/// Main sequence lifetime (Gyr) of a star of `initial_mass` solar masses and
+/// [Fe/H]; metal-rich stars are more opaque, burn more slowly and live longer
+pub fn stellar_lifetime(initial_mass: f64, metallicity: f64) -> f64 {
+    main_sequence_lifetime(initial_mass) * 10f64.powf(0.1 * metallicity)
+}
+
+/// Most massive star (solar masses) of the given [Fe/H] still on the main
+/// sequence at `age` Gyr
+pub fn turnoff_mass(age: f64, metallicity: f64) -> f64 {
+    (10.0 * 10f64.powf(0.1 * metallicity) / age).powf(0.4)
+}
+
+/// Stage reached at `age` Gyr by a star of `initial_mass` solar masses and [Fe/H]
+pub fn evolutionary_stage(initial_mass: f64, metallicity: f64, age: f64) -> EvolutionaryStage {
+    let lifetime = stellar_lifetime(initial_mass, metallicity);
+    if initial_mass < 0.08 || age < lifetime {
+        EvolutionaryStage::MainSequence
+    } else if age < lifetime * (1.0 + GIANT_PHASE) {
+        EvolutionaryStage::GiantBranch
+    } else {
+        EvolutionaryStage::Remnant
+    }
+}
+
+/// Mass, radius and luminosity at `age` Gyr of a star born with `initial_mass`
+/// solar masses and the given [Fe/H]
+pub fn evolve(initial_mass: f64, metallicity: f64, age: f64) -> EvolvedState {
+    let stage = evolutionary_stage(initial_mass, metallicity, age);
+    let lifetime = stellar_lifetime(initial_mass, metallicity);
+
+    let (mass, radius, luminosity) = match stage {
+        // Brown dwarfs never ignite hydrogen and simply cool
+        EvolutionaryStage::MainSequence if initial_mass < 0.08 => {
+            (initial_mass, 0.1, brown_dwarf_luminosity(initial_mass, age))
+        }
+        // Helium ash raises the core temperature: the Sun has brightened ~40%
+        EvolutionaryStage::MainSequence => {
+            let progress = age / lifetime;
+            (
+                initial_mass,
+                main_sequence_radius(initial_mass) * (0.9 + 0.22 * progress),
+                main_sequence_luminosity(initial_mass) * (0.72 + 0.6 * progress),
+            )
+        }
+        EvolutionaryStage::GiantBranch => {
+            let progress = (age - lifetime) / (lifetime * GIANT_PHASE);
+            let (radius, luminosity) = giant_branch(initial_mass, progress);
+            (initial_mass, radius, luminosity)
+        }
+        EvolutionaryStage::Remnant => {
+            let cooling_age = age - lifetime * (1.0 + GIANT_PHASE);
+            let mass = remnant_mass(initial_mass);
+            match initial_mass {
+                // White dwarfs fade as they radiate stored heat (Mestel cooling)
+                m if m < 8.0 => (
+                    mass,
+                    0.0126 * mass.powf(-1.0 / 3.0),
+                    (1e-3 * (mass / 0.6) * cooling_age.max(1e-6).powf(-1.4)).min(100.0),
+                ),
+                m if m < 25.0 => {
+                    let temperature = (1e6 * (cooling_age / 1e-4).max(1e-6).powf(-0.25)).clamp(3e4, 3e6);
+                    let radius = NEUTRON_STAR_RADIUS / SOLAR_RADIUS;
+                    (mass, radius, blackbody_luminosity(radius, temperature))
+                }
+                _ => (mass, schwarzschild_radius(mass) / SOLAR_RADIUS, 0.0),
+            }
+        }
+    };
+
+    EvolvedState { stage, mass, radius, luminosity }
+}
+
+/// Radius and luminosity (solar units) a fraction `progress` of the way up the
+/// giant branch
+fn giant_branch(initial_mass: f64, progress: f64) -> (f64, f64) {
+    let radius = main_sequence_radius(initial_mass) * 1.12;
+    let luminosity = main_sequence_luminosity(initial_mass) * 1.32;
+    match initial_mass {
+        // Low- and intermediate-mass stars climb the red giant branch, growing a
+        // hundredfold in size and a thousandfold in brightness
+        m if m < 8.0 => (radius * 10f64.powf(2.0 * progress), (luminosity * 10f64.powf(3.0 * progress)).min(1e4 * m)),
+        // Massive stars cross to red supergiants at nearly constant luminosity
+        m if m < 30.0 => (radius * 10f64.powf(2.2 * progress), luminosity * (1.0 + progress)),
+        _ => (radius * 10f64.powf(1.5 * progress), luminosity * (1.0 + progress)),
+    }
+}
+
+/// Event horizon radius in metres of a black hole of `mass` solar masses
+pub fn schwarzschild_radius(mass: f64) -> f64 {
+    2.0 * 6.674e-11 * (mass * 1.989e30) / (299_792_458.0f64.powi(2))
+}
+
+/// Largest radius (AU) a star of `initial_mass` solar masses has reached by `age`
+pub fn maximum_radius(initial_mass: f64, metallicity: f64, age: f64) -> f64 {
+    let lifetime = stellar_lifetime(initial_mass, metallicity);
+    let age = age.min(lifetime * (1.0 + GIANT_PHASE) * 0.9999);
+    evolve(initial_mass, metallicity, age).radius * SOLAR_RADIUS / AU
+}
+
+impl StellarType {
+    /// Stage of life in which stars of this type are found
+    pub fn stage(&self) -> EvolutionaryStage {
+        match self {
+            _ if self.is_main_sequence() => EvolutionaryStage::MainSequence,
+            _ if self.is_remnant() => EvolutionaryStage::Remnant,
+            _ => EvolutionaryStage::GiantBranch,
+        }
+    }
+
+    /// Initial masses (solar) of stars that become this type
+    pub(crate) fn initial_mass_range(&self) -> (f64, f64) {
+        match self {
+            StellarType::RedGiant => (0.8, 3.0),
+            StellarType::SuperGiant => (8.0, 30.0),
+            StellarType::HyperGiant => (30.0, 60.0),
+            StellarType::WhiteDwarfRemnant => (0.8, 8.0),
+            StellarType::NeutronStar | StellarType::QuarkStar |
+            StellarType::PulsarStar | StellarType::MagnetarStar => (8.0, 25.0),
+            StellarType::BlackHole => (25.0, 60.0),
+            _ => self.mass_range(),
+        }
+    }
+
+    /// Initial masses (solar) of stars of the given [Fe/H] that are this type at
+    /// `age` Gyr, if there are any
+    pub(crate) fn initial_mass_window(&self, age: f64, metallicity: f64) -> Option<(f64, f64)> {
+        let (lightest, heaviest) = self.initial_mass_range();
+        let turnoff = turnoff_mass(age, metallicity);
+        let (lightest, heaviest) = match self.stage() {
+            _ if *self == StellarType::BrownDwarf => (lightest, heaviest),
+            EvolutionaryStage::MainSequence => (lightest, heaviest.min(turnoff)),
+            EvolutionaryStage::GiantBranch => (
+                lightest.max(turnoff),
+                heaviest.min(turnoff_mass(age / (1.0 + GIANT_PHASE), metallicity)),
+            ),
+            EvolutionaryStage::Remnant => (
+                lightest.max(turnoff_mass(age / (1.0 + GIANT_PHASE), metallicity)),
+                heaviest,
+            ),
+        };
+        (lightest < heaviest).then_some((lightest, heaviest))
+    }
+
+    /// Ages (Gyr) at which a star born with `initial_mass` solar masses is this
+    /// type, if any fall within the age of the universe
+    pub(crate) fn age_window(&self, initial_mass: f64, metallicity: f64) -> Option<(f64, f64)> {
+        const UNIVERSE_AGE: f64 = 13.8;
+        let lifetime = stellar_lifetime(initial_mass, metallicity);
+        let (youngest, oldest) = match self.stage() {
+            _ if *self == StellarType::BrownDwarf => (0.1, UNIVERSE_AGE),
+            EvolutionaryStage::MainSequence => (0.1f64.min(lifetime / 2.0), lifetime.min(UNIVERSE_AGE)),
+            EvolutionaryStage::GiantBranch => (lifetime, (lifetime * (1.0 + GIANT_PHASE)).min(UNIVERSE_AGE)),
+            EvolutionaryStage::Remnant => (lifetime * (1.0 + GIANT_PHASE), UNIVERSE_AGE),
+        };
+        (youngest < oldest).then_some((youngest, oldest))
+    }
+}
+
+impl Star {
+    pub fn evolutionary_stage(&self) -> EvolutionaryStage {
+        evolutionary_stage(self.initial_mass, self.metallicity, self.age)
+    }
+
+    /// Largest radius (AU) the star has reached, which for remnants is the tip of
+    /// their giant branch
+    pub fn maximum_radius(&self) -> f64 {
+        maximum_radius(self.initial_mass, self.metallicity, self.age)
+            .max(self.physical.radius / AU)
+    }
+}
+
+impl Planet {
+    /// Expand the orbit as the host's mass falls from `initial_mass` to
+    /// `final_mass` solar masses; slow mass loss conserves a·M
+    pub fn adjust_orbit_for_mass_loss(&mut self, initial_mass: f64, final_mass: f64) {
+        if initial_mass == final_mass {
+            return;
+        }
+        let orbit = self.orbit;
+        self.orbit = Orbit::new(
+            orbit.semi_major_axis * initial_mass / final_mass,
+            orbit.eccentricity,
+            orbit.inclination,
+            orbit.longitude_of_ascending_node,
+            orbit.argument_of_periapsis,
+            orbit.mean_anomaly_at_epoch,
+            final_mass,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generate, PlanetHost, SolarSystem, StellarType};
+
+    #[test]
+    fn test_sun_evolution() {
+        let now = evolve(1.0, 0.0, 4.6);
+        assert_eq!(now.stage, EvolutionaryStage::MainSequence);
+        assert!((now.luminosity - 1.0).abs() < 0.02);
+        assert!((now.radius - 1.0).abs() < 0.02);
+
+        let giant = evolve(1.0, 0.0, 10.9);
+        assert_eq!(giant.stage, EvolutionaryStage::GiantBranch);
+        assert!(giant.radius > 50.0 && giant.luminosity > 500.0);
+
+        let white_dwarf = evolve(1.0, 0.0, 13.0);
+        assert_eq!(white_dwarf.stage, EvolutionaryStage::Remnant);
+        assert!(white_dwarf.mass > 0.5 && white_dwarf.mass < 0.6);
+        assert!(white_dwarf.luminosity < 0.01);
+    }
+
+    #[test]
+    fn test_remnants_follow_initial_mass() {
+        assert!(evolve(3.0, 0.0, 13.0).radius < 0.02);
+        assert!((evolve(15.0, 0.0, 1.0).mass - 1.4).abs() < 1e-9);
+        let black_hole = evolve(40.0, 0.0, 1.0);
+        assert_eq!(black_hole.luminosity, 0.0);
+        assert!(black_hole.mass > 3.0);
+
+        // White dwarfs cool over time
+        assert!(evolve(2.0, 0.0, 5.0).luminosity > evolve(2.0, 0.0, 13.0).luminosity);
+        // Metal-poor stars leave the main sequence sooner
+        assert!(stellar_lifetime(1.0, -1.0) < stellar_lifetime(1.0, 0.0));
+        assert!((turnoff_mass(main_sequence_lifetime(2.0), 0.0) - 2.0).abs() < 1e-9);
+        assert!((turnoff_mass(stellar_lifetime(2.0, -1.0), -1.0) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_no_star_outlives_its_type() {
+        for system in (0..3000).map(SolarSystem::generate_with_seed) {
+            for star in &system.stars {
+                let stage = star.evolutionary_stage();
+                match &star.stellar_type {
+                    StellarType::BrownDwarf => {}
+                    t if t.is_main_sequence() => assert_eq!(stage, EvolutionaryStage::MainSequence, "{:?}", t),
+                    StellarType::RedGiant | StellarType::SuperGiant | StellarType::HyperGiant =>
+                        assert_eq!(stage, EvolutionaryStage::GiantBranch),
+                    _ => assert_eq!(stage, EvolutionaryStage::Remnant),
+                }
+                assert!(star.age <= 13.8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_evolved_hosts_clear_inner_planets() {
+        let systems: Vec<SolarSystem> = (0..3000)
+            .map(SolarSystem::generate_with_seed)
+            .filter(|s| s.star().evolutionary_stage() != EvolutionaryStage::MainSequence)
+            .collect();
+        assert!(systems.iter().any(|s| !s.planets.is_empty()));
+
+        for system in &systems {
+            let star = system.star();
+            for planet in system.planets.iter().filter(|p| p.host == PlanetHost::Star(0)) {
+                // Survivors formed beyond the envelope's reach, then spread out
+                let formed_at = planet.orbit.periapsis() * star.physical.mass / (star.initial_mass * 1.989e30);
+                assert!(formed_at >= 2.0 * star.maximum_radius());
+            }
+        }
+    }
+
+    #[test]
+    fn test_mass_loss_widens_orbits() {
+        let mut planet = Planet::generate_at_distance(5, 1.0);
+        planet.adjust_orbit_for_mass_loss(1.0, 0.5);
+        assert!((planet.orbit.semi_major_axis - 2.0).abs() < 1e-9);
+        assert!((planet.orbital_period() - Orbit::kepler_period(2.0, 0.5)).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use rand::prelude::*;
-use crate::{SolarSystem, Generate};
+use crate::SolarSystem;
+use crate::noise::fbm;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GalacticPosition {
@@ -26,75 +27,46 @@ pub struct GalacticRegion {
     pub metallicity: f64,      // [Fe/H] relative to solar
     pub star_density: f64,     // stars per cubic parsec
     pub spiral_phase: f64,     // phase in spiral arm
+    pub arm_index: Option<usize>, // spiral arm the region lies in, if any
+    pub in_arm: bool,
+    pub young_star_fraction: f64, // fraction of stars younger than ~1 Gyr
+    pub star_forming: bool,    // inside an active star-forming arm segment
 }
 
 impl GalacticRegion {
+    /// Generate a region of the Milky Way at the given position (parsecs)
     pub fn generate_at_position(x: f64, y: f64, z: f64) -> Self {
+        Self::generate_in_galaxy(&Galaxy::milky_way(), x, y, z)
+    }
+
+    /// Generate a region at the given position (parsecs) using the structure of `galaxy`
+    pub fn generate_in_galaxy(galaxy: &Galaxy, x: f64, y: f64, z: f64) -> Self {
         let r = (x * x + y * y).sqrt();
         let theta = y.atan2(x);
 
-        // Determine population type based on position
-        let population = if r < 3000.0 && z.abs() < 1000.0 {
-            PopulationType::Bulge
-        } else if z.abs() > 1000.0 {
-            PopulationType::Halo
-        } else if z.abs() > 400.0 {
-            PopulationType::ThickDisk
-        } else {
-            PopulationType::ThinDisk
-        };
+        let population = galaxy.population_at(x, y, z);
+        let metallicity = galaxy.metallicity_at(&population, r);
+        let density = galaxy.density_at(&population, x, y, z);
 
-        // Calculate metallicity gradient
-        // Decreases with radius and distance from plane
-        let base_metallicity = match population {
-            PopulationType::ThinDisk => 0.0,  // Solar metallicity at solar radius
-            PopulationType::ThickDisk => -0.5,
-            PopulationType::Bulge => 0.3,
-            PopulationType::Halo => -1.5,
-        };
-        
-        // Add radial gradient (-0.07 dex/kpc)
-        let r_kpc = r / 1000.0;
-        let solar_r = 8.0; // kpc
-        let metallicity = base_metallicity - 0.07 * (r_kpc - solar_r);
+        let (spiral_phase, nearest_arm, arm_strength) =
+            if population == PopulationType::ThinDisk && galaxy.has_spiral_arms() {
+                let (arm, strength) = galaxy.arm_profile(r, theta);
+                (galaxy.spiral_phase_at(r, theta), arm, strength)
+            } else {
+                (0.0, 0, 0.0)
+            };
 
-        // Calculate star density
-        let density = match population {
-            PopulationType::ThinDisk => {
-                // Exponential disk with 2.6 kpc scale length
-                let scale_height = 300.0; // pc
-                let scale_length = 2600.0; // pc
-                0.1 * (-r/scale_length - z.abs()/scale_height).exp()
-            },
-            PopulationType::ThickDisk => {
-                let scale_height = 900.0;
-                let scale_length = 3600.0;
-                0.02 * (-r/scale_length - z.abs()/scale_height).exp()
-            },
-            PopulationType::Bulge => {
-                // de Vaucouleurs profile
-                let r_eff = 2500.0; // pc
-                0.5 * (-7.67 * ((r*r + (z/0.5)*(z/0.5)).sqrt()/r_eff).powf(0.25)).exp()
-            },
-            PopulationType::Halo => {
-                // r^-3.5 profile
-                let r_spherical = (r*r + z*z).sqrt();
-                1e-4 * (r_spherical/8000.0).powf(-3.5)
-            },
-        };
-
-        // Calculate spiral arm phase
-        // Using 4-arm logarithmic spiral with pitch angle 12.5°
-        let pitch_angle = 12.5f64.to_radians();
-        let spiral_phase = if population == PopulationType::ThinDisk {
-            let k = pitch_angle.tan();
-            let base_phase = theta - (r.ln() / k);
-            // Normalize to [0, 2π)
-            (base_phase % (2.0 * std::f64::consts::PI) + 2.0 * std::f64::consts::PI) 
-                % (2.0 * std::f64::consts::PI)
-        } else {
-            0.0
+        // Arms (or clumps, in irregulars) are where the disk's young stars are born
+        let clumping = galaxy.clump_factor(x, y, z);
+        let in_arm = arm_strength > 0.5;
+        let young_star_fraction = match population {
+            PopulationType::ThinDisk => galaxy.thin_disk.young_star_fraction
+                * (1.0 + galaxy.arm_contrast * arm_strength) * clumping,
+            PopulationType::ThickDisk => galaxy.thick_disk.young_star_fraction * clumping,
+            PopulationType::Bulge | PopulationType::Halo => 0.0,
         };
+        let star_forming = arm_strength > 0.8
+            || (population == PopulationType::ThinDisk && clumping > 1.0 + 0.5 * galaxy.clumpiness);
 
         GalacticRegion {
             position: GalacticPosition { x, y, z, r, theta },
@@ -102,6 +74,10 @@ impl GalacticRegion {
             metallicity,
             star_density: density,
             spiral_phase,
+            arm_index: in_arm.then_some(nearest_arm),
+            in_arm,
+            young_star_fraction: young_star_fraction.min(1.0),
+            star_forming,
         }
     }
 
@@ -109,40 +85,431 @@ impl GalacticRegion {
         let mut rng = StdRng::seed_from_u64(seed);
         
         // Probability of star generation based on density
-        let prob = self.star_density / 0.1; // Normalize to thin disk maximum
+        let prob = self.star_density / REFERENCE_STAR_DENSITY;
         
         if rng.gen::<f64>() > prob {
             return None;
         }
 
-        // Generate system with appropriate metallicity
-        let system = SolarSystem::generate_with_seed(seed);
+        // Generate system with the region's metallicity and stellar population
+        let system = SolarSystem::generate_in_region(self, seed);
         Some(system)
     }
 }
 
+/// Star density (stars/pc³) at which a region is always occupied by a system
+const REFERENCE_STAR_DENSITY: f64 = 0.1;
+
+/// Hubble-sequence class of a galaxy, selecting its population and density models
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum GalaxyMorphology {
+    Spiral,        // Bulge, thin/thick disk and spiral arms
+    BarredSpiral,  // Spiral whose arms start at the ends of a central bar
+    Lenticular,    // S0: bulge and old disk without arms
+    Elliptical,    // Single Sérsic spheroid, no disk
+    Irregular,     // Clumpy disk without bulge or arms
+}
+
+/// Exponential disk component: density falls off as exp(-r/h_r - |z|/h_z)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskProfile {
+    pub central_density: f64,  // stars per cubic parsec at the galactic centre
+    pub scale_length: f64,     // parsecs
+    pub scale_height: f64,     // parsecs
+    pub base_metallicity: f64, // [Fe/H] at the reference radius
+    pub young_star_fraction: f64, // fraction of stars younger than ~1 Gyr (inter-arm)
+}
+
+impl DiskProfile {
+    fn density(&self, r: f64, z: f64) -> f64 {
+        self.central_density * (-r / self.scale_length - z.abs() / self.scale_height).exp()
+    }
+}
+
+/// Flattened Sérsic spheroid; the bulge of disk galaxies or the body of an elliptical
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulgeProfile {
+    pub central_density: f64,  // normalisation of the profile
+    pub effective_radius: f64, // parsecs
+    pub axis_ratio: f64,       // vertical flattening (c/a)
+    pub sersic_index: f64,     // 4 for a de Vaucouleurs profile
+    pub base_metallicity: f64, // [Fe/H] at the reference radius
+}
+
+impl BulgeProfile {
+    /// Radius of the ellipsoidal shell through (r, z)
+    fn ellipsoidal_radius(&self, r: f64, z: f64) -> f64 {
+        let z_scaled = z / self.axis_ratio;
+        (r * r + z_scaled * z_scaled).sqrt()
+    }
+
+    fn density(&self, r: f64, z: f64) -> f64 {
+        // Ciotti & Bertin (1999) approximation of the Sérsic b_n constant
+        let n = self.sersic_index;
+        let b_n = 2.0 * n - 1.0 / 3.0 + 4.0 / (405.0 * n);
+        let m = self.ellipsoidal_radius(r, z);
+        self.central_density * (-b_n * (m / self.effective_radius).powf(1.0 / n)).exp()
+    }
+}
+
+/// Triaxial central bar of a barred spiral
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarProfile {
+    pub central_density: f64,  // stars per cubic parsec at the centre of the bar
+    pub semi_major_axis: f64,  // parsecs
+    pub axis_ratio: f64,       // minor / major axis
+    pub angle: f64,            // degrees from the x axis
+}
+
+impl BarProfile {
+    /// Radius in units of the bar's semi-major axis (1 at the bar's edge)
+    fn bar_radius(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let along = x * cos + y * sin;
+        let across = -x * sin + y * cos;
+        let minor = self.semi_major_axis * self.axis_ratio;
+        ((along / self.semi_major_axis).powi(2) + (across / minor).powi(2) + (z / minor).powi(2)).sqrt()
+    }
+
+    fn density(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.central_density * (-2.0 * self.bar_radius(x, y, z)).exp()
+    }
+}
+
+/// Power-law stellar halo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaloProfile {
+    pub reference_density: f64, // stars per cubic parsec at the reference radius
+    pub reference_radius: f64,  // parsecs
+    pub power_law_index: f64,   // density falls as r^-n
+    pub base_metallicity: f64,  // [Fe/H] at the reference radius
+    pub outer_radius: f64,      // parsecs, where the halo ends
+}
+
+impl HaloProfile {
+    fn density(&self, r: f64, z: f64) -> f64 {
+        let r_spherical = (r * r + z * z).sqrt();
+        self.reference_density * (r_spherical / self.reference_radius).powf(-self.power_law_index)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Galaxy {
-    pub radius: f64,           // parsecs
-    pub disk_height: f64,      // parsecs
+    pub morphology: GalaxyMorphology,
+    pub seed: u64,             // drives clumpy structure and star sampling
+    pub radius: f64,           // parsecs, edge of the disk
+    pub disk_height: f64,      // parsecs, thick disk / halo boundary
     pub bulge_radius: f64,     // parsecs
     pub spiral_arms: usize,
     pub pitch_angle: f64,      // degrees
+    pub arm_width: f64,        // parsecs, gaussian width across an arm
+    pub arm_contrast: f64,     // arm density enhancement over the inter-arm disk
+    pub thin_disk_height: f64, // parsecs, thin disk / thick disk boundary
+    pub solar_radius: f64,     // parsecs, reference radius for metallicities
+    pub metallicity_gradient: f64, // dex per kpc
+    pub clumpiness: f64,       // 0-1 amplitude of density clumps (irregulars)
+    pub clump_scale: f64,      // parsecs, typical size of a clump
+    pub thin_disk: DiskProfile,
+    pub thick_disk: DiskProfile,
+    pub bulge: BulgeProfile,
+    pub bar: BarProfile,
+    pub halo: HaloProfile,
+}
+
+impl Default for Galaxy {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Galaxy {
     pub fn new() -> Self {
+        Self::milky_way()
+    }
+
+    /// Parameters tuned to the Milky Way (the default galaxy)
+    pub fn milky_way() -> Self {
         Galaxy {
+            morphology: GalaxyMorphology::Spiral,
+            seed: 0,
             radius: 50000.0,       // 50 kpc
             disk_height: 1000.0,    // 1 kpc
             bulge_radius: 3000.0,   // 3 kpc
             spiral_arms: 4,
             pitch_angle: 12.5,
+            arm_width: 400.0,
+            arm_contrast: 1.5,
+            thin_disk_height: 400.0,
+            solar_radius: 8000.0,
+            metallicity_gradient: -0.07,
+            clumpiness: 0.0,
+            clump_scale: 500.0,
+            thin_disk: DiskProfile {
+                central_density: 0.1,
+                scale_length: 2600.0,
+                scale_height: 300.0,
+                base_metallicity: 0.0, // Solar metallicity at solar radius
+                young_star_fraction: 0.1,
+            },
+            thick_disk: DiskProfile {
+                central_density: 0.02,
+                scale_length: 3600.0,
+                scale_height: 900.0,
+                base_metallicity: -0.5,
+                young_star_fraction: 0.01,
+            },
+            bulge: BulgeProfile {
+                central_density: 0.5,
+                effective_radius: 2500.0,
+                axis_ratio: 0.5,
+                sersic_index: 4.0,
+                base_metallicity: 0.3,
+            },
+            bar: BarProfile {
+                central_density: 0.0,
+                semi_major_axis: 0.0,
+                axis_ratio: 1.0,
+                angle: 0.0,
+            },
+            halo: HaloProfile {
+                reference_density: 1e-4,
+                reference_radius: 8000.0,
+                power_law_index: 3.5,
+                base_metallicity: -1.5,
+                outer_radius: 100000.0, // 100 kpc
+            },
+        }
+    }
+
+    /// Representative galaxy of the given morphology
+    pub fn with_morphology(morphology: GalaxyMorphology) -> Self {
+        let milky_way = Self::milky_way();
+        match morphology {
+            GalaxyMorphology::Spiral => milky_way,
+            GalaxyMorphology::BarredSpiral => Galaxy {
+                morphology,
+                bulge_radius: 1500.0,
+                spiral_arms: 2,
+                pitch_angle: 14.0,
+                bar: BarProfile {
+                    central_density: 0.4,
+                    semi_major_axis: 4000.0,
+                    axis_ratio: 0.35,
+                    angle: 27.0,
+                },
+                ..milky_way
+            },
+            GalaxyMorphology::Lenticular => Galaxy {
+                morphology,
+                bulge_radius: 4000.0,
+                spiral_arms: 0,
+                arm_contrast: 0.0,
+                thin_disk: DiskProfile {
+                    central_density: 0.08,
+                    base_metallicity: 0.1,
+                    young_star_fraction: 0.005,
+                    ..milky_way.thin_disk
+                },
+                thick_disk: DiskProfile {
+                    central_density: 0.03,
+                    young_star_fraction: 0.0,
+                    ..milky_way.thick_disk
+                },
+                bulge: BulgeProfile {
+                    central_density: 0.6,
+                    effective_radius: 3000.0,
+                    ..milky_way.bulge
+                },
+                ..milky_way
+            },
+            GalaxyMorphology::Elliptical => Galaxy {
+                morphology,
+                radius: 30000.0,
+                bulge_radius: 30000.0,  // the spheroid fills the galaxy
+                spiral_arms: 0,
+                arm_contrast: 0.0,
+                metallicity_gradient: -0.1,
+                thin_disk: DiskProfile {
+                    central_density: 0.0,
+                    young_star_fraction: 0.0,
+                    ..milky_way.thin_disk
+                },
+                thick_disk: DiskProfile {
+                    central_density: 0.0,
+                    young_star_fraction: 0.0,
+                    ..milky_way.thick_disk
+                },
+                bulge: BulgeProfile {
+                    central_density: 1.0,
+                    effective_radius: 4000.0,
+                    axis_ratio: 0.7,
+                    sersic_index: 4.0,
+                    base_metallicity: 0.2,
+                },
+                ..milky_way
+            },
+            GalaxyMorphology::Irregular => Galaxy {
+                morphology,
+                radius: 5000.0,
+                disk_height: 1500.0,
+                bulge_radius: 0.0,
+                spiral_arms: 0,
+                arm_contrast: 0.0,
+                thin_disk_height: 500.0,
+                solar_radius: 2000.0,
+                metallicity_gradient: 0.0,
+                clumpiness: 0.8,
+                clump_scale: 300.0,
+                thin_disk: DiskProfile {
+                    central_density: 0.05,
+                    scale_length: 1500.0,
+                    scale_height: 400.0,
+                    base_metallicity: -0.6,
+                    young_star_fraction: 0.3,
+                },
+                thick_disk: DiskProfile {
+                    central_density: 0.01,
+                    scale_length: 2000.0,
+                    scale_height: 800.0,
+                    base_metallicity: -0.9,
+                    young_star_fraction: 0.05,
+                },
+                halo: HaloProfile {
+                    reference_density: 1e-5,
+                    reference_radius: 2000.0,
+                    outer_radius: 20000.0,
+                    ..milky_way.halo
+                },
+                ..milky_way
+            },
         }
     }
 
     pub fn generate_region(&self, x: f64, y: f64, z: f64) -> GalacticRegion {
-        GalacticRegion::generate_at_position(x, y, z)
+        GalacticRegion::generate_in_galaxy(self, x, y, z)
+    }
+
+    /// Whether the galaxy's thin disk carries a spiral pattern
+    pub fn has_spiral_arms(&self) -> bool {
+        matches!(self.morphology, GalaxyMorphology::Spiral | GalaxyMorphology::BarredSpiral)
+            && self.spiral_arms > 0
+    }
+
+    fn in_bar(&self, x: f64, y: f64, z: f64) -> bool {
+        self.morphology == GalaxyMorphology::BarredSpiral && self.bar.bar_radius(x, y, z) < 1.0
+    }
+
+    /// Dominant stellar population at the given position (parsecs)
+    pub fn population_at(&self, x: f64, y: f64, z: f64) -> PopulationType {
+        let r = (x * x + y * y).sqrt();
+
+        if self.morphology == GalaxyMorphology::Elliptical {
+            // One spheroidal population, fading into the halo
+            return if self.bulge.ellipsoidal_radius(r, z) < self.bulge_radius {
+                PopulationType::Bulge
+            } else {
+                PopulationType::Halo
+            };
+        }
+
+        if self.in_bar(x, y, z) || (r < self.bulge_radius && z.abs() < self.disk_height) {
+            PopulationType::Bulge
+        } else if z.abs() > self.disk_height || r > self.radius {
+            PopulationType::Halo
+        } else if z.abs() > self.thin_disk_height {
+            PopulationType::ThickDisk
+        } else {
+            PopulationType::ThinDisk
+        }
+    }
+
+    /// [Fe/H] of a population at cylindrical radius `r` (parsecs)
+    pub fn metallicity_at(&self, population: &PopulationType, r: f64) -> f64 {
+        let base_metallicity = match population {
+            PopulationType::ThinDisk => self.thin_disk.base_metallicity,
+            PopulationType::ThickDisk => self.thick_disk.base_metallicity,
+            PopulationType::Bulge => self.bulge.base_metallicity,
+            PopulationType::Halo => self.halo.base_metallicity,
+        };
+
+        // Radial gradient relative to the reference radius
+        base_metallicity + self.metallicity_gradient * (r - self.solar_radius) / 1000.0
+    }
+
+    /// Star density (stars/pc³) of a population at the given position (parsecs)
+    pub fn density_at(&self, population: &PopulationType, x: f64, y: f64, z: f64) -> f64 {
+        let r = (x * x + y * y).sqrt();
+        let theta = y.atan2(x);
+
+        match population {
+            PopulationType::ThinDisk => {
+                let (_, arm_strength) = self.arm_profile(r, theta);
+                self.thin_disk.density(r, z)
+                    * (1.0 + self.arm_contrast * arm_strength)
+                    * self.clump_factor(x, y, z)
+            },
+            PopulationType::ThickDisk => self.thick_disk.density(r, z) * self.clump_factor(x, y, z),
+            PopulationType::Bulge => {
+                let bar = if self.in_bar(x, y, z) { self.bar.density(x, y, z) } else { 0.0 };
+                self.bulge.density(r, z) + bar
+            },
+            PopulationType::Halo if (r * r + z * z).sqrt() > self.halo.outer_radius => 0.0,
+            PopulationType::Halo => {
+                // The Sérsic envelope of an elliptical continues into its halo
+                let envelope = if self.morphology == GalaxyMorphology::Elliptical {
+                    self.bulge.density(r, z)
+                } else {
+                    0.0
+                };
+                self.halo.density(r, z) + envelope
+            },
+        }
+    }
+
+    /// Phase within the logarithmic spiral pattern, normalised to [0, 2π)
+    pub fn spiral_phase_at(&self, r: f64, theta: f64) -> f64 {
+        let k = self.pitch_angle.to_radians().tan();
+        let base_phase = if self.morphology == GalaxyMorphology::BarredSpiral && self.bar.semi_major_axis > 0.0 {
+            // Arms wind outward from the ends of the bar; without one they
+            // follow the plain spiral
+            theta - self.bar.angle.to_radians() - (r.max(1.0) / self.bar.semi_major_axis).ln() / k
+        } else {
+            theta - (r.max(1.0).ln() / k)
+        };
+        base_phase.rem_euclid(2.0 * std::f64::consts::PI)
+    }
+
+    /// Nearest spiral arm and the gaussian arm profile (1 on the arm ridge, → 0 between arms)
+    pub fn arm_profile(&self, r: f64, theta: f64) -> (usize, f64) {
+        if !self.has_spiral_arms() {
+            return (0, 0.0);
+        }
+
+        // Arms are evenly spaced in spiral phase
+        let arm_spacing = 2.0 * std::f64::consts::PI / self.spiral_arms as f64;
+        let arm_position = self.spiral_phase_at(r, theta) / arm_spacing;
+        let nearest = arm_position.round();
+        let arm_index = nearest as usize % self.spiral_arms;
+
+        // Phase offset converted to a distance perpendicular to the arm
+        let phase_offset = (arm_position - nearest) * arm_spacing;
+        let distance = r * phase_offset.abs() * self.pitch_angle.to_radians().sin();
+        let strength = (-distance * distance / (2.0 * self.arm_width * self.arm_width)).exp();
+
+        (arm_index, strength)
+    }
+
+    /// Multiplicative density fluctuation from star-forming clumps (1 when smooth)
+    pub fn clump_factor(&self, x: f64, y: f64, z: f64) -> f64 {
+        if self.clumpiness <= 0.0 {
+            return 1.0;
+        }
+
+        let scale = self.clump_scale;
+        let n = fbm(self.seed, x / scale, y / scale, z / scale, 4, 2.0, 0.5);
+        // fbm clusters around 0.5; stretch it so clumps and voids reach the full amplitude
+        let contrast = ((n - 0.5) * 4.0).clamp(-1.0, 1.0);
+        1.0 + self.clumpiness * contrast
     }
 }
 
@@ -194,6 +561,12 @@ mod tests {
         let plane = GalacticRegion::generate_at_position(8000.0, 0.0, 0.0);
         let above = GalacticRegion::generate_at_position(8000.0, 0.0, 500.0);
         assert!(plane.star_density > above.star_density);
+
+        // The halo ends at its outer radius
+        let edge = GalacticRegion::generate_at_position(90000.0, 0.0, 0.0);
+        let beyond = GalacticRegion::generate_at_position(110000.0, 0.0, 0.0);
+        assert!(edge.star_density > 0.0);
+        assert_eq!(beyond.star_density, 0.0);
     }
 
     #[test]
@@ -210,6 +583,147 @@ mod tests {
         assert_eq!(halo.spiral_phase, 0.0);
     }
 
+    #[test]
+    fn test_galaxy_parameters_drive_regions() {
+        let milky_way = Galaxy::milky_way();
+        let default_region = GalacticRegion::generate_at_position(2000.0, 0.0, 0.0);
+        let region = milky_way.generate_region(2000.0, 0.0, 0.0);
+        assert_eq!(region.population, default_region.population);
+        assert_eq!(region.star_density, default_region.star_density);
+
+        // A galaxy with a compact bulge puts the same point in the disk
+        let compact = Galaxy { bulge_radius: 1000.0, ..Galaxy::milky_way() };
+        assert_eq!(compact.generate_region(2000.0, 0.0, 0.0).population, PopulationType::ThinDisk);
+
+        // A steeper gradient makes the outer disk more metal-poor
+        let steep = Galaxy { metallicity_gradient: -0.2, ..Galaxy::milky_way() };
+        assert!(steep.generate_region(12000.0, 0.0, 0.0).metallicity
+            < milky_way.generate_region(12000.0, 0.0, 0.0).metallicity);
+
+        // Beyond the disk edge only the halo remains
+        let small = Galaxy { radius: 10000.0, ..Galaxy::milky_way() };
+        assert_eq!(small.generate_region(12000.0, 0.0, 0.0).population, PopulationType::Halo);
+    }
+
+    #[test]
+    fn test_spiral_arm_contrast() {
+        let galaxy = Galaxy::milky_way();
+        let regions: Vec<GalacticRegion> = (0..360)
+            .map(|deg| {
+                let theta = (deg as f64).to_radians();
+                galaxy.generate_region(8000.0 * theta.cos(), 8000.0 * theta.sin(), 0.0)
+            })
+            .collect();
+
+        let arm: Vec<&GalacticRegion> = regions.iter().filter(|r| r.in_arm).collect();
+        let inter_arm: Vec<&GalacticRegion> = regions.iter().filter(|r| !r.in_arm).collect();
+        assert!(!arm.is_empty() && !inter_arm.is_empty());
+
+        let mean = |set: &[&GalacticRegion], f: fn(&GalacticRegion) -> f64| {
+            set.iter().map(|r| f(r)).sum::<f64>() / set.len() as f64
+        };
+        assert!(mean(&arm, |r| r.star_density) > mean(&inter_arm, |r| r.star_density));
+        assert!(mean(&arm, |r| r.young_star_fraction) > mean(&inter_arm, |r| r.young_star_fraction));
+        assert!(regions.iter().filter(|r| r.star_forming).all(|r| r.in_arm));
+
+        // Every arm is crossed once going around the disk
+        let mut arms: Vec<usize> = arm.iter().filter_map(|r| r.arm_index).collect();
+        arms.sort();
+        arms.dedup();
+        assert_eq!(arms.len(), galaxy.spiral_arms);
+
+        // Without contrast the disk is axisymmetric
+        let flat = Galaxy { arm_contrast: 0.0, ..Galaxy::milky_way() };
+        let a = flat.generate_region(8000.0, 0.0, 0.0);
+        let b = flat.generate_region(0.0, 8000.0, 0.0);
+        assert!((a.star_density - b.star_density).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_elliptical_has_no_disk() {
+        let galaxy = Galaxy::with_morphology(GalaxyMorphology::Elliptical);
+        for x in [0.0, 2000.0, 8000.0, 20000.0] {
+            let region = galaxy.generate_region(x, 0.0, 0.0);
+            assert!(matches!(region.population, PopulationType::Bulge | PopulationType::Halo));
+            assert_eq!(region.spiral_phase, 0.0);
+            assert_eq!(region.young_star_fraction, 0.0);
+        }
+
+        // Sérsic profile falls off monotonically
+        let inner = galaxy.generate_region(1000.0, 0.0, 0.0);
+        let outer = galaxy.generate_region(10000.0, 0.0, 0.0);
+        assert!(inner.star_density > outer.star_density);
+    }
+
+    #[test]
+    fn test_lenticular_has_no_arms() {
+        let galaxy = Galaxy::with_morphology(GalaxyMorphology::Lenticular);
+        for deg in 0..36 {
+            let theta = (deg as f64 * 10.0).to_radians();
+            let region = galaxy.generate_region(8000.0 * theta.cos(), 8000.0 * theta.sin(), 0.0);
+            assert_eq!(region.population, PopulationType::ThinDisk);
+            assert!(!region.in_arm && !region.star_forming);
+            assert!(region.young_star_fraction < Galaxy::milky_way().thin_disk.young_star_fraction);
+        }
+    }
+
+    #[test]
+    fn test_barred_spiral_bar_feeds_arms() {
+        let galaxy = Galaxy::with_morphology(GalaxyMorphology::BarredSpiral);
+        let (sin, cos) = galaxy.bar.angle.to_radians().sin_cos();
+
+        // Along the bar beyond the bulge we are still in the bar population
+        let along = galaxy.generate_region(2500.0 * cos, 2500.0 * sin, 0.0);
+        assert_eq!(along.population, PopulationType::Bulge);
+
+        // Perpendicular to the bar at the same radius is disk
+        let across = galaxy.generate_region(-2500.0 * sin, 2500.0 * cos, 0.0);
+        assert_eq!(across.population, PopulationType::ThinDisk);
+
+        // Both arms begin at the bar tips
+        let a = galaxy.bar.semi_major_axis * 1.05;
+        assert!(galaxy.generate_region(a * cos, a * sin, 0.0).in_arm);
+        assert!(galaxy.generate_region(-a * cos, -a * sin, 0.0).in_arm);
+
+        // A barred spiral built without a bar winds like a plain spiral
+        let unbarred = Galaxy {
+            bar: BarProfile { semi_major_axis: 0.0, ..galaxy.bar.clone() },
+            ..galaxy.clone()
+        };
+        let spiral = Galaxy { morphology: GalaxyMorphology::Spiral, ..unbarred.clone() };
+        for (r, theta) in [(0.0, 0.0), (500.0, 1.0), (8000.0, -2.0)] {
+            let phase = unbarred.spiral_phase_at(r, theta);
+            assert!(phase.is_finite());
+            assert_eq!(phase, spiral.spiral_phase_at(r, theta));
+        }
+        let region = unbarred.generate_region(8000.0, 0.0, 0.0);
+        assert_eq!(region.population, PopulationType::ThinDisk);
+        assert!(region.spiral_phase.is_finite() && region.star_density.is_finite());
+    }
+
+    #[test]
+    fn test_irregular_is_clumpy() {
+        let galaxy = Galaxy::with_morphology(GalaxyMorphology::Irregular);
+        assert_ne!(galaxy.generate_region(0.0, 0.0, 0.0).population, PopulationType::Bulge);
+
+        let densities: Vec<f64> = (0..72)
+            .map(|deg| {
+                let theta = (deg as f64 * 5.0).to_radians();
+                galaxy.generate_region(1500.0 * theta.cos(), 1500.0 * theta.sin(), 0.0).star_density
+            })
+            .collect();
+        let max = densities.iter().cloned().fold(f64::MIN, f64::max);
+        let min = densities.iter().cloned().fold(f64::MAX, f64::min);
+        assert!(max / min > 1.5, "irregular density should vary at fixed radius");
+
+        // Clumps are a deterministic function of position and seed
+        let a = galaxy.generate_region(1200.0, 300.0, 50.0);
+        let b = galaxy.generate_region(1200.0, 300.0, 50.0);
+        assert_eq!(a.star_density, b.star_density);
+        let reseeded = Galaxy { seed: 99, ..galaxy.clone() };
+        assert_ne!(reseeded.generate_region(1200.0, 300.0, 50.0).star_density, a.star_density);
+    }
+
     #[test]
     fn test_deterministic_generation() {
         let region = GalacticRegion::generate_at_position(8000.0, 0.0, 0.0);
@@ -221,12 +735,12 @@ mod tests {
         // Same seed should give same result
         assert_eq!(system1.is_some(), system2.is_some());
         if let (Some(s1), Some(s2)) = (&system1, system2) {
-            assert_eq!(s1.star.stellar_type, s2.star.stellar_type);
+            assert_eq!(s1.star().stellar_type, s2.star().stellar_type);
         }
 
         // Different seeds should usually give different results
         if let (Some(s1), Some(s3)) = (&system1, system3) {
-            assert!(s1.star.stellar_type != s3.star.stellar_type || 
+            assert!(s1.star().stellar_type != s3.star().stellar_type || 
                    s1.planets.len() != s3.planets.len());
         }
     }
@@ -7,7 +7,7 @@ pub trait GalaxyGeneration {
 
 impl GalaxyGeneration for Galaxy {
     fn generate_region(&self, x: f64, y: f64, z: f64) -> GalacticRegion {
-        GalacticRegion::generate_at_position(x, y, z)
+        GalacticRegion::generate_in_galaxy(self, x, y, z)
     }
 
     fn generate_solar_system(&self, region: &GalacticRegion, seed: u64) -> Option<SolarSystem> {
@@ -0,0 +1,285 @@
+//! Graded habitability: how far a planet's conditions allow liquid surface
+//! water, scored factor by factor against the Earth.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::{PhysicalProperties, Planet, PlanetType};
+
+/// Score below which a single factor rules a planet out
+pub const FAILING_SCORE: f64 = 0.1;
+
+const EARTH_RADIUS: f64 = 6.371e6;            // m
+const EARTH_DENSITY: f64 = 5514.0;            // kg/m³
+const EARTH_ESCAPE_VELOCITY: f64 = 11186.0;   // m/s
+const EARTH_TEMPERATURE: f64 = 288.0;         // K, mean surface
+const STANDARD_GRAVITY: f64 = 9.80665;        // m/s²
+
+/// One of the conditions a habitability report scores
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum HabitabilityFactor {
+    EarthSimilarity,
+    StellarFlux,
+    Temperature,
+    Gravity,
+    Pressure,
+    Water,
+    MagneticField,
+    TidalLocking,
+    StellarActivity,
+}
+
+impl HabitabilityFactor {
+    pub const ALL: [HabitabilityFactor; 9] = [
+        HabitabilityFactor::EarthSimilarity,
+        HabitabilityFactor::StellarFlux,
+        HabitabilityFactor::Temperature,
+        HabitabilityFactor::Gravity,
+        HabitabilityFactor::Pressure,
+        HabitabilityFactor::Water,
+        HabitabilityFactor::MagneticField,
+        HabitabilityFactor::TidalLocking,
+        HabitabilityFactor::StellarActivity,
+    ];
+}
+
+impl fmt::Display for HabitabilityFactor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            HabitabilityFactor::EarthSimilarity => "bulk properties unlike the Earth's",
+            HabitabilityFactor::StellarFlux => "orbit outside the habitable zone",
+            HabitabilityFactor::Temperature => "surface too hot or cold for liquid water",
+            HabitabilityFactor::Gravity => "surface gravity too weak or strong",
+            HabitabilityFactor::Pressure => "atmosphere too thin or thick for liquid water",
+            HabitabilityFactor::Water => "no liquid surface water",
+            HabitabilityFactor::MagneticField => "no magnetic field to shield the atmosphere",
+            HabitabilityFactor::TidalLocking => "tidally locked to its star",
+            HabitabilityFactor::StellarActivity => "harsh stellar flares and ultraviolet",
+        })
+    }
+}
+
+/// What a planet's star or stars provide, as seen from its orbit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HostConditions {
+    pub habitable_zone: (f64, f64),  // in AU, as in `SolarSystem::habitable_zone`
+    pub stellar_temperature: f64,    // in K, of the (primary) star orbited
+    pub age: f64,                    // in billions of years
+    pub ultraviolet_flux: f64,       // relative to the Earth's
+}
+
+/// Per-factor habitability scores, each from 0 (hostile) to 1 (Earth-like)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitabilityReport {
+    pub earth_similarity: f64,  // Earth Similarity Index
+    pub stellar_flux: f64,
+    pub temperature: f64,
+    pub gravity: f64,
+    pub pressure: f64,
+    pub water: f64,
+    pub magnetic_field: f64,
+    pub tidal_locking: f64,
+    pub stellar_activity: f64,
+    pub overall: f64,  // geometric mean of the factors
+    pub failures: Vec<HabitabilityFactor>,  // factors scoring below `FAILING_SCORE`
+}
+
+impl HabitabilityReport {
+    pub fn score(&self, factor: HabitabilityFactor) -> f64 {
+        match factor {
+            HabitabilityFactor::EarthSimilarity => self.earth_similarity,
+            HabitabilityFactor::StellarFlux => self.stellar_flux,
+            HabitabilityFactor::Temperature => self.temperature,
+            HabitabilityFactor::Gravity => self.gravity,
+            HabitabilityFactor::Pressure => self.pressure,
+            HabitabilityFactor::Water => self.water,
+            HabitabilityFactor::MagneticField => self.magnetic_field,
+            HabitabilityFactor::TidalLocking => self.tidal_locking,
+            HabitabilityFactor::StellarActivity => self.stellar_activity,
+        }
+    }
+
+    /// Habitable when no single factor rules the planet out
+    pub fn is_habitable(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// 0 outside `zero_low..zero_high`, 1 within `one_low..one_high`, linear between
+fn trapezoid(x: f64, zero_low: f64, one_low: f64, one_high: f64, zero_high: f64) -> f64 {
+    if x <= zero_low || x >= zero_high {
+        0.0
+    } else if x < one_low {
+        (x - zero_low) / (one_low - zero_low)
+    } else if x > one_high {
+        (zero_high - x) / (zero_high - one_high)
+    } else {
+        1.0
+    }
+}
+
+/// Earth Similarity Index of a body's radius, density, escape velocity and
+/// surface temperature (Schulze-Makuch et al. 2011): 1 for the Earth, ~0.7 for Mars
+pub fn earth_similarity_index(physical: &PhysicalProperties) -> f64 {
+    let terms = [
+        (physical.radius, EARTH_RADIUS, 0.57),
+        (physical.density, EARTH_DENSITY, 1.07),
+        (physical.escape_velocity, EARTH_ESCAPE_VELOCITY, 0.70),
+        (physical.surface_temperature, EARTH_TEMPERATURE, 5.58),
+    ];
+    terms.iter().map(|&(x, earth, weight)| {
+        (1.0 - ((x - earth) / (x + earth)).abs()).powf(weight / terms.len() as f64)
+    }).product()
+}
+
+/// Billions of years a star of `temperature` K stays magnetically active and
+/// flaring; fully convective M dwarfs stay active longest (West et al. 2008)
+fn activity_lifetime(temperature: f64) -> f64 {
+    match temperature {
+        t if t >= 5000.0 => 1.0,
+        t if t >= 3900.0 => 1.5,
+        t if t >= 3300.0 => 2.0,
+        _ => 8.0,
+    }
+}
+
+impl Planet {
+    /// Earth Similarity Index of the planet
+    pub fn earth_similarity_index(&self) -> f64 {
+        earth_similarity_index(&self.physical)
+    }
+
+    /// Score every habitability factor of the planet under `host`
+    pub fn habitability_report(&self, host: &HostConditions) -> HabitabilityReport {
+        let mass = self.physical.mass / 5.972e24;
+        let distance = self.orbit.semi_major_axis;
+        let (inner, outer) = host.habitable_zone;
+
+        // The conservative zone, tapering to the optimistic recent-Venus and
+        // early-Mars limits (Kopparapu et al. 2013)
+        let stellar_flux = trapezoid(distance, 0.8 * inner, inner, outer, 1.2 * outer);
+        let temperature = trapezoid(self.physical.surface_temperature, 240.0, 270.0, 310.0, 373.0);
+        let gravity = trapezoid(self.physical.surface_gravity / STANDARD_GRAVITY, 0.2, 0.5, 1.5, 3.0);
+        // Liquid water needs at least the triple-point pressure
+        let pressure = self.atmosphere.as_ref()
+            .map(|atm| trapezoid(atm.pressure, 0.006, 0.3, 5.0, 20.0))
+            .unwrap_or(0.0);
+
+        let water = match self.planet_type {
+            _ if self.planet_type.is_giant() => 0.0,
+            PlanetType::DesertWorld | PlanetType::LavaWorld | PlanetType::ChthonianPlanet => 0.0,
+            PlanetType::IceWorld => 0.3,  // liquid only beneath the ice
+            // A deep global ocean seals the rock away from weathering and nutrients
+            PlanetType::OceanWorld if self.water_fraction > 0.1 => 0.6,
+            PlanetType::OceanWorld => 1.0,
+            _ => (self.water_fraction.log10() + 5.0).clamp(0.0, 1.0),
+        };
+
+        // A core dynamo needs a large enough planet to stay molten, and
+        // strengthens with rapid rotation; giants always have one
+        let magnetic_field = if self.planet_type.is_giant() {
+            1.0
+        } else {
+            let molten_core = ((mass - 0.2) / 0.4).clamp(0.0, 1.0);
+            let rotation = ((100.0 - self.rotation_period) / 90.0).clamp(0.0, 1.0);
+            molten_core * (0.5 + 0.5 * rotation)
+        };
+
+        // Permanent day and night sides can still hold water near the terminator
+        let tidal_locking = if self.tidally_locked { 0.5 } else { 1.0 };
+
+        // Young and cool stars flare; close orbits take more ultraviolet
+        let maturity = 0.25 + 0.75 * (host.age / activity_lifetime(host.stellar_temperature)).clamp(0.0, 1.0);
+        let ultraviolet = 1.0 - ((host.ultraviolet_flux / 5.0).log10() / 20f64.log10()).clamp(0.0, 1.0);
+        let stellar_activity = maturity.min(ultraviolet);
+
+        let mut report = HabitabilityReport {
+            earth_similarity: self.earth_similarity_index(),
+            stellar_flux,
+            temperature,
+            gravity,
+            pressure,
+            water,
+            magnetic_field,
+            tidal_locking,
+            stellar_activity,
+            overall: 0.0,
+            failures: Vec::new(),
+        };
+        let scores = HabitabilityFactor::ALL.map(|factor| report.score(factor));
+        report.overall = scores.iter().product::<f64>().powf(1.0 / scores.len() as f64);
+        report.failures = HabitabilityFactor::ALL.into_iter()
+            .filter(|&factor| report.score(factor) < FAILING_SCORE)
+            .collect();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::habitable_zone_range;
+    use crate::tests::{earth_analogue, sun};
+
+    #[test]
+    fn test_earth_similarity_index() {
+        let earth = PhysicalProperties {
+            mass: 5.972e24,
+            radius: EARTH_RADIUS,
+            surface_temperature: EARTH_TEMPERATURE,
+            density: EARTH_DENSITY,
+            surface_gravity: 9.81,
+            escape_velocity: EARTH_ESCAPE_VELOCITY,
+        };
+        assert!((earth_similarity_index(&earth) - 1.0).abs() < 1e-9);
+
+        let mars = PhysicalProperties {
+            mass: 6.42e23,
+            radius: 3.39e6,
+            surface_temperature: 227.0,
+            density: 3934.0,
+            surface_gravity: 3.72,
+            escape_velocity: 5027.0,
+        };
+        let esi = earth_similarity_index(&mars);
+        assert!((esi - 0.7).abs() < 0.1, "{}", esi);
+    }
+
+    #[test]
+    fn test_habitability_reports() {
+        let earth = earth_analogue(1.0, 1.0);
+        let report = earth.habitability_report(&sun());
+        assert!(report.is_habitable(), "{:?}", report);
+        assert!(report.overall > 0.8 && report.gravity == 1.0 && report.stellar_flux == 1.0, "{:?}", report);
+
+        // Venus is too hot, Mars too thin and cold
+        let venus = earth_analogue(0.815, 0.723).habitability_report(&sun());
+        assert!(venus.failures.contains(&HabitabilityFactor::Temperature), "{:?}", venus);
+        assert!(venus.failures.contains(&HabitabilityFactor::StellarFlux));
+        let mars = earth_analogue(0.107, 1.524).habitability_report(&sun());
+        assert!(mars.failures.contains(&HabitabilityFactor::Pressure), "{:?}", mars);
+        assert!(mars.failures.contains(&HabitabilityFactor::MagneticField));
+        assert!(!mars.is_habitable() && mars.overall == 0.0);
+
+        // A young, flaring red dwarf locks its planets and bathes them in ultraviolet
+        let red_dwarf = HostConditions {
+            habitable_zone: habitable_zone_range(0.2, 0.005),
+            stellar_temperature: 3100.0,
+            age: 0.5,
+            ultraviolet_flux: 1.0,
+        };
+        let mut close = earth.clone();
+        close.orbit = crate::Orbit::new(0.07, 0.0, 0.0, 0.0, 0.0, 0.0, 0.2);
+        close.despin(0.2, red_dwarf.age);
+        assert!(close.tidally_locked);
+        let mut despun = earth.clone();
+        despun.despin(1.0, 4.6);
+        assert!(!despun.tidally_locked && despun.rotation_period == 1.0);
+        let report = close.habitability_report(&red_dwarf);
+        assert_eq!(report.tidal_locking, 0.5);
+        assert!(report.stellar_activity < 0.5 && report.overall < 0.9);
+
+        for factor in HabitabilityFactor::ALL {
+            assert!(!factor.to_string().is_empty());
+        }
+    }
+}
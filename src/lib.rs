@@ -3,11 +3,28 @@ use std::f64::consts::PI;
 
 pub mod distributions;
 pub mod planet;
+pub mod atmosphere;
+pub mod habitability;
+pub mod surface;
+pub mod climate;
+pub mod moon;
+pub mod rings;
 pub mod solar_system;
+pub mod binary;
+pub mod evolution;
+pub mod spectral;
+pub mod photometry;
+pub mod sky;
 pub mod small_bodies;
 pub mod small_body_generation;
 pub mod galaxy;
 pub mod galaxy_generation;
+pub mod noise;
+pub mod sector;
+pub mod spatial;
+pub mod seed;
+pub mod orbit;
+pub mod ephemeris;
 
 #[cfg(test)]
 mod tests;
@@ -20,6 +37,14 @@ pub struct Position {
     pub z: f64,
 }
 
+/// Represents a 3D velocity in metres per second
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
 /// Chemical composition of a celestial body
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Composition {
@@ -79,11 +104,30 @@ pub trait Generate {
 
 // Re-export commonly used types
 pub use planet::{Planet, PlanetType, Atmosphere};
-pub use solar_system::{SolarSystem, Star, StellarType};
+pub use atmosphere::AtmosphericComposition;
+pub use habitability::{HabitabilityFactor, HabitabilityReport, HostConditions};
+pub use surface::{Heightmap, Surface};
+pub use climate::{Biome, BiomeMap, Climate};
+pub use moon::{Moon, MoonType};
+pub use rings::{RingSystem, RingGap};
+pub use solar_system::{SolarSystem, Star, StellarType, StellarEnvironment};
+pub use binary::PlanetHost;
+pub use distributions::InitialMassFunction;
+pub use evolution::{EvolutionaryStage, EvolvedState};
+pub use spectral::{SpectralClass, HarvardClass, LuminosityClass, WhiteDwarfClass, ParseSpectralClassError};
 pub use small_bodies::{SmallBody, SmallBodyType, ElementDistribution};
 pub use small_body_generation::SmallBodyGeneration;
-pub use galaxy::{Galaxy, GalacticRegion, GalacticPosition, PopulationType};
+pub use galaxy::{
+    Galaxy, GalaxyMorphology, GalacticRegion, GalacticPosition, PopulationType,
+    DiskProfile, BulgeProfile, BarProfile, HaloProfile,
+};
 pub use galaxy_generation::GalaxyGeneration;
+pub use sector::{SectorCoord, StarId, StarStub, SECTOR_SIZE};
+pub use spatial::NearbyStar;
+pub use sky::{SkyObject, SkyObjectKind, SkyView};
+pub use seed::{SeedPath, SeedLevel};
+pub use orbit::Orbit;
+pub use ephemeris::{BodyState, SystemState};
 
 #[cfg(test)]
 mod unit_tests {
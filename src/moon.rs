@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+use rand::prelude::*;
+use rand_distr::Poisson;
+use std::f64::consts::PI;
+use crate::{Composition, Orbit, PhysicalProperties, Planet};
+use crate::distributions::{calculate_surface_temperature, moon_probability, snow_line};
+use crate::orbit::{AU, G, SOLAR_MASS};
+use crate::seed::SeedPath;
+
+const EARTH_MASS: f64 = 5.972e24;  // kg
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MoonType {
+    Regular,    // Formed in the planet's disk: close, circular, prograde orbits
+    Irregular,  // Captured: distant, eccentric, often retrograde orbits
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Moon {
+    pub id: u64,
+    pub name: String,
+    pub moon_type: MoonType,
+    pub physical: PhysicalProperties,
+    pub orbit: Orbit,              // around the parent planet
+    pub rotation_period: f64,      // in Earth days
+    pub tidally_locked: bool,
+    pub composition: Composition,
+}
+
+/// Radius of a planet's Hill sphere in AU, inside which it can hold satellites
+pub fn hill_radius(semi_major_axis: f64, eccentricity: f64, planet_mass: f64, star_mass: f64) -> f64 {
+    semi_major_axis * (1.0 - eccentricity) * (planet_mass / (3.0 * star_mass)).cbrt()
+}
+
+/// Distance (metres) inside which a fluid satellite of `satellite_density` is
+/// torn apart by a planet of the given radius and density
+pub fn roche_limit(planet_radius: f64, planet_density: f64, satellite_density: f64) -> f64 {
+    2.44 * planet_radius * (planet_density / satellite_density).cbrt()
+}
+
+/// Tidal quality factor Q of a solid body
+const TIDAL_QUALITY: f64 = 100.0;
+
+/// A body's rotation before its host's tides slow it (s⁻¹); a 12 hour day
+const PRIMORDIAL_SPIN: f64 = 2.0 * PI / (12.0 * 3600.0);
+
+/// Rigidity (N/m²) of rock, and of ice
+pub(crate) const ROCK_RIGIDITY: f64 = 3e10;
+pub(crate) const ICE_RIGIDITY: f64 = 4e9;
+
+/// Time in years for a host of `host_mass` to despin a body `semi_major_axis`
+/// away into synchronous rotation (Gladman et al. 1996), with masses in kg,
+/// lengths in metres and the body's `rigidity` in N/m²
+pub fn tidal_locking_time(semi_major_axis: f64, radius: f64, mass: f64, host_mass: f64, rigidity: f64) -> f64 {
+    let gravity = G * mass / radius.powi(2);
+    let density = mass / (4.0 / 3.0 * PI * radius.powi(3));
+    // Love number of a uniform elastic sphere
+    let love_number = 1.5 / (1.0 + 19.0 * rigidity / (2.0 * density * gravity * radius));
+    let moment_of_inertia = 0.4 * mass * radius.powi(2);
+    let seconds = PRIMORDIAL_SPIN * semi_major_axis.powi(6) * moment_of_inertia * TIDAL_QUALITY
+        / (3.0 * G * host_mass.powi(2) * love_number * radius.powi(5));
+    seconds / 3.156e7
+}
+
+impl Planet {
+    /// Radius of the planet's Hill sphere in AU around a star of `star_mass` solar masses
+    pub fn hill_radius(&self, star_mass: f64) -> f64 {
+        hill_radius(
+            self.orbit.semi_major_axis,
+            self.orbit.eccentricity,
+            self.physical.mass / SOLAR_MASS,
+            star_mass,
+        )
+    }
+
+    /// Populate the planet's satellites for a star of `star_mass` solar masses,
+    /// `star_luminosity` and `system_age` Gyr
+    pub fn generate_moons(&mut self, star_mass: f64, star_luminosity: f64, system_age: f64) {
+        let path = SeedPath::from_planet_seed(self.seed);
+        let mut rng = path.stream("moons").rng();
+        self.moons.clear();
+
+        let mass_earths = self.physical.mass / EARTH_MASS;
+        if rng.gen::<f64>() >= moon_probability(mass_earths) {
+            return;
+        }
+
+        let hill = self.hill_radius(star_mass) * AU;
+        let beyond_snow_line = self.orbit.semi_major_axis > snow_line(star_luminosity);
+
+        // Regular moons form in the planet's disk; solid planets instead get
+        // one or two from giant impacts
+        let regular_count = if self.planet_type.is_giant() {
+            1 + Poisson::new(0.4 * mass_earths.powf(0.4)).unwrap().sample(&mut rng) as usize
+        } else if rng.gen::<f64>() < 0.2 {
+            2
+        } else {
+            1
+        };
+        // Larger Hill spheres capture more passing bodies
+        let capture_rate = (4.0 * hill / AU).min(20.0) + 1e-9;
+        let irregular_count = Poisson::new(capture_rate).unwrap().sample(&mut rng) as usize;
+
+        // Regular moons stay well inside the Hill sphere, outside the Roche limit
+        let mut distance = roche_limit(self.physical.radius, self.physical.density, 3000.0)
+            .max(3.0 * self.physical.radius) * rng.gen_range(1.5..3.0);
+        for _ in 0..regular_count {
+            if distance > 0.3 * hill {
+                break;
+            }
+            let index = self.moons.len();
+            let moon = self.generate_moon(
+                path.moon(index), MoonType::Regular, distance, beyond_snow_line,
+                star_luminosity, system_age,
+            );
+            self.moons.push(moon);
+            distance *= rng.gen_range(1.5..2.5);
+        }
+
+        // Captured orbits with eccentricities up to 0.5 dip to half their
+        // semi-major axis; close-in planets are too small to hold those that
+        // would cross the Roche limit of the fluffiest (icy) capture
+        let closest_capture = 2.0 * roche_limit(self.physical.radius, self.physical.density, 1000.0)
+            .max(self.physical.radius);
+        for _ in 0..irregular_count {
+            let distance = rng.gen_range(0.1..0.5) * hill;
+            if distance < closest_capture {
+                continue;
+            }
+            let index = self.moons.len();
+            // Captured bodies come from anywhere in the system
+            let icy = rng.gen::<f64>() < 0.5;
+            let moon = self.generate_moon(
+                path.moon(index), MoonType::Irregular, distance, icy,
+                star_luminosity, system_age,
+            );
+            self.moons.push(moon);
+        }
+    }
+
+    fn generate_moon(
+        &self,
+        path: SeedPath,
+        moon_type: MoonType,
+        distance: f64,
+        icy: bool,
+        star_luminosity: f64,
+        system_age: f64,
+    ) -> Moon {
+        let mut rng = path.rng();
+        let planet_mass = self.physical.mass;
+
+        let mass = match (&moon_type, &self.planet_type) {
+            // Giant impacts leave moons of around a percent of the planet's mass
+            (MoonType::Regular, planet_type) if !planet_type.is_giant() => planet_mass * rng.gen_range(0.001..0.015),
+            // Giant planet satellite systems hold ~1e-4 of the planet's mass
+            (MoonType::Regular, _) => planet_mass * 10f64.powf(rng.gen_range(-5.5..-3.7)),
+            (MoonType::Irregular, _) => 10f64.powf(rng.gen_range(15.0..19.0)),
+        };
+        let density: f64 = if icy { rng.gen_range(1000.0..2000.0) } else { rng.gen_range(2800.0..3500.0) };
+        let radius = (3.0 * mass / (4.0 * PI * density)).cbrt();
+
+        let orbit = match moon_type {
+            MoonType::Regular => Orbit::new(
+                distance / AU,
+                rng.gen_range(0.0..0.01),
+                rng.gen_range(0.0..1f64.to_radians()),
+                rng.gen_range(0.0..2.0 * PI),
+                rng.gen_range(0.0..2.0 * PI),
+                rng.gen_range(0.0..2.0 * PI),
+                planet_mass / SOLAR_MASS,
+            ),
+            // Captured orbits are eccentric and frequently retrograde
+            MoonType::Irregular => Orbit::new(
+                distance / AU,
+                rng.gen_range(0.1..0.5),
+                rng.gen_range(0.0..PI),
+                rng.gen_range(0.0..2.0 * PI),
+                rng.gen_range(0.0..2.0 * PI),
+                rng.gen_range(0.0..2.0 * PI),
+                planet_mass / SOLAR_MASS,
+            ),
+        };
+
+        let rigidity = if icy { ICE_RIGIDITY } else { ROCK_RIGIDITY };
+        let locking_time = tidal_locking_time(distance, radius, mass, planet_mass, rigidity);
+        let tidally_locked = locking_time < system_age * 1e9;
+        let rotation_period = if tidally_locked {
+            orbit.period * 365.25
+        } else {
+            rng.gen_range(0.2..2.0)
+        };
+
+        let bond_albedo = if icy { 0.5 } else { 0.11 };  // Europa-like ice, or bare rock like the Moon
+        let composition = if icy {
+            Composition { hydrogen: 0.0, helium: 0.0, metallicity: 0.4, other: 0.6 } // ice and volatiles
+        } else {
+            Composition { hydrogen: 0.0, helium: 0.0, metallicity: 0.9, other: 0.1 }
+        };
+
+        let mut physical = PhysicalProperties {
+            mass,
+            radius,
+            surface_temperature: calculate_surface_temperature(
+                &mut rng, self.orbit.semi_major_axis, star_luminosity, bond_albedo, 0.0,
+            ),
+            density,
+            surface_gravity: 0.0,  // Will be calculated
+            escape_velocity: 0.0,  // Will be calculated
+        };
+        physical.surface_gravity = physical.calculate_surface_gravity();
+        physical.escape_velocity = physical.calculate_escape_velocity();
+
+        Moon {
+            id: path.id(),
+            name: format!("Moon-{:016X}", path.id()),
+            moon_type,
+            physical,
+            orbit,
+            rotation_period,
+            tidally_locked,
+            composition,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlanetType;
+    use crate::tests::first_seed;
+
+    fn planet_with_moons(distance: f64, min_mass: f64) -> Planet {
+        first_seed(
+            "a planet with moons",
+            |seed| {
+                let mut planet = Planet::generate_at_distance(seed, distance);
+                planet.generate_moons(1.0, 1.0, 4.5);
+                planet
+            },
+            |p| p.physical.mass > min_mass * EARTH_MASS && !p.moons.is_empty(),
+        )
+    }
+
+    #[test]
+    fn test_hill_sphere() {
+        // Earth's Hill sphere is about 0.01 AU
+        let earth = hill_radius(1.0, 0.0167, 3.0e-6, 1.0);
+        assert!((earth - 0.0098).abs() < 0.001);
+        // Jupiter's is far larger
+        assert!(hill_radius(5.2, 0.048, 9.5e-4, 1.0) > 30.0 * earth);
+    }
+
+    #[test]
+    fn test_moons_are_bound() {
+        for seed in 0..200 {
+            let mut planet = Planet::generate_at_distance(seed, 5.0);
+            planet.generate_moons(1.0, 1.0, 4.5);
+            let hill = planet.hill_radius(1.0);
+
+            for moon in &planet.moons {
+                assert!(moon.orbit.apoapsis() < hill);
+                let roche = roche_limit(planet.physical.radius, planet.physical.density, moon.physical.density);
+                assert!(moon.orbit.periapsis() * AU > roche);
+                assert!(moon.physical.mass < planet.physical.mass);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hot_jupiter_moons_clear_the_roche_limit() {
+        // Close in, a giant's Hill sphere is only a few times its Roche limit
+        let mut moons = 0;
+        for (seed, distance) in (0..300).flat_map(|seed| [(seed, 0.05), (seed, 0.1), (seed, 0.2)]) {
+            let mut planet = Planet::generate_at_distance(seed, 5.0);
+            if !planet.planet_type.is_giant() {
+                continue;
+            }
+            planet.orbit.semi_major_axis = distance;
+            planet.generate_moons(1.0, 1.0, 4.5);
+            for moon in &planet.moons {
+                let roche = roche_limit(planet.physical.radius, planet.physical.density, moon.physical.density);
+                assert!(moon.orbit.periapsis() * AU > roche, "{:?} moon inside the Roche limit", moon.moon_type);
+                assert!(moon.orbit.apoapsis() < planet.hill_radius(1.0));
+            }
+            moons += planet.moons.len();
+        }
+        assert!(moons > 0);
+    }
+
+    #[test]
+    fn test_giants_have_more_moons() {
+        let count_moons = |distance: f64, planet_type: PlanetType| {
+            let planets: Vec<Planet> = (0..500)
+                .map(|seed| {
+                    let mut planet = Planet::generate_at_distance(seed, distance);
+                    planet.generate_moons(1.0, 1.0, 4.5);
+                    planet
+                })
+                .filter(|p| p.planet_type == planet_type)
+                .collect();
+            planets.iter().map(|p| p.moons.len()).sum::<usize>() as f64 / planets.len() as f64
+        };
+
+        assert!(count_moons(5.0, PlanetType::GasGiant) > count_moons(1.0, PlanetType::Terrestrial));
+    }
+
+    #[test]
+    fn test_regular_moons_and_locking() {
+        let giant = planet_with_moons(5.0, 100.0);
+        let regular: Vec<&Moon> = giant.moons.iter().filter(|m| m.moon_type == MoonType::Regular).collect();
+        assert!(!regular.is_empty());
+
+        // Close regular moons of a giant lock quickly and are icy beyond the snow line
+        let inner = regular[0];
+        assert!(inner.tidally_locked);
+        assert!((inner.rotation_period - inner.orbit.period * 365.25).abs() < 1e-9);
+        assert!(inner.orbit.inclination < 1f64.to_radians());
+        assert!(inner.physical.density < 2000.0);
+
+        // Inside the snow line regular moons are rocky
+        let rocky = planet_with_moons(1.0, 0.5);
+        for moon in rocky.moons.iter().filter(|m| m.moon_type == MoonType::Regular) {
+            assert!(moon.physical.density > 2500.0);
+        }
+    }
+
+    #[test]
+    fn test_moons_deterministic() {
+        let mut a = Planet::generate_at_distance(11, 5.0);
+        let mut b = Planet::generate_at_distance(11, 5.0);
+        a.generate_moons(1.0, 1.0, 4.5);
+        b.generate_moons(1.0, 1.0, 4.5);
+        assert_eq!(a.moons.len(), b.moons.len());
+        for (x, y) in a.moons.iter().zip(&b.moons) {
+            assert_eq!(x.id, y.id);
+            assert_eq!(x.physical.mass, y.physical.mass);
+        }
+    }
+}
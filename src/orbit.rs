@@ -0,0 +1,319 @@
+//! Keplerian orbits around a central star or planet.
+//!
+//! Elements are stored in AU and radians; positions are returned in metres to
+//! match the rest of the crate. The reference plane is the system's x-y plane.
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use crate::{Position, Velocity};
+
+pub(crate) const AU: f64 = 1.496e11;          // metres
+pub(crate) const SOLAR_MASS: f64 = 1.989e30;  // kg
+pub(crate) const G: f64 = 6.67430e-11;        // gravitational constant
+
+/// Seconds in a year of a body 1 AU from one solar mass, so that periods in
+/// years follow Kepler's third law exactly with the constants above
+fn seconds_per_year() -> f64 {
+    2.0 * PI * (AU.powi(3) / (G * SOLAR_MASS)).sqrt()
+}
+
+/// Classical orbital elements of a body bound to a star or planet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Orbit {
+    pub semi_major_axis: f64,              // in AU
+    pub eccentricity: f64,
+    pub inclination: f64,                  // in radians, from the system plane
+    pub longitude_of_ascending_node: f64,  // in radians
+    pub argument_of_periapsis: f64,        // in radians
+    pub mean_anomaly_at_epoch: f64,        // in radians, at time zero
+    pub period: f64,                       // in Earth years, from Kepler's third law
+}
+
+impl Orbit {
+    /// Orbit with the given elements around a central body of `central_mass`
+    /// solar masses
+    pub fn new(
+        semi_major_axis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        longitude_of_ascending_node: f64,
+        argument_of_periapsis: f64,
+        mean_anomaly_at_epoch: f64,
+        central_mass: f64,
+    ) -> Self {
+        Orbit {
+            semi_major_axis,
+            eccentricity,
+            inclination,
+            longitude_of_ascending_node,
+            argument_of_periapsis,
+            mean_anomaly_at_epoch,
+            period: Self::kepler_period(semi_major_axis, central_mass),
+        }
+    }
+
+    /// Orbital period in Earth years of a body `semi_major_axis` AU from a central
+    /// body of `central_mass` solar masses
+    pub fn kepler_period(semi_major_axis: f64, central_mass: f64) -> f64 {
+        (semi_major_axis.powi(3) / central_mass).sqrt()
+    }
+
+    /// Elements of the orbit passing through `position` (metres) with `velocity`
+    /// (m/s) around a central body of `central_mass` solar masses. The state must
+    /// be bound.
+    pub fn from_state_vectors(position: Position, velocity: Velocity, central_mass: f64) -> Self {
+        let mu = G * central_mass * SOLAR_MASS;
+        let r = [position.x, position.y, position.z];
+        let v = [velocity.x, velocity.y, velocity.z];
+        let r_len = norm(r);
+        let v_sq = dot(v, v);
+
+        let h = cross(r, v);
+        let h_hat = scale(h, 1.0 / norm(h));
+        let inclination = h_hat[2].clamp(-1.0, 1.0).acos();
+
+        // The ascending node is undefined for orbits in the reference plane;
+        // measure angles from the x axis instead
+        let node = cross([0.0, 0.0, 1.0], h);
+        let node_hat = if norm(node) > 1e-12 * norm(h) {
+            scale(node, 1.0 / norm(node))
+        } else {
+            [1.0, 0.0, 0.0]
+        };
+        let longitude_of_ascending_node = node_hat[1].atan2(node_hat[0]).rem_euclid(2.0 * PI);
+        let in_plane = cross(h_hat, node_hat);
+
+        let e_vec = scale(
+            sub(scale(r, v_sq - mu / r_len), scale(v, dot(r, v))),
+            1.0 / mu,
+        );
+        let eccentricity = norm(e_vec);
+        let argument_of_periapsis = if eccentricity > 1e-12 {
+            dot(e_vec, in_plane).atan2(dot(e_vec, node_hat)).rem_euclid(2.0 * PI)
+        } else {
+            0.0
+        };
+
+        let true_anomaly = dot(r, in_plane).atan2(dot(r, node_hat)) - argument_of_periapsis;
+        let eccentric_anomaly = ((1.0 - eccentricity * eccentricity).sqrt() * true_anomaly.sin())
+            .atan2(eccentricity + true_anomaly.cos());
+        let mean_anomaly = (eccentric_anomaly - eccentricity * eccentric_anomaly.sin()).rem_euclid(2.0 * PI);
+
+        let semi_major_axis = 1.0 / (2.0 / r_len - v_sq / mu) / AU;
+
+        Orbit::new(
+            semi_major_axis,
+            eccentricity,
+            inclination,
+            longitude_of_ascending_node,
+            argument_of_periapsis,
+            mean_anomaly,
+            central_mass,
+        )
+    }
+
+    /// Mean anomaly (radians) `time` Earth years after the epoch
+    pub fn mean_anomaly_at(&self, time: f64) -> f64 {
+        (self.mean_anomaly_at_epoch + 2.0 * PI * time / self.period).rem_euclid(2.0 * PI)
+    }
+
+    /// Solve Kepler's equation M = E - e sin E for the eccentric anomaly
+    pub fn eccentric_anomaly(&self, mean_anomaly: f64) -> f64 {
+        let e = self.eccentricity;
+        let mut anomaly = if e < 0.8 { mean_anomaly } else { PI };
+        for _ in 0..50 {
+            let step = (anomaly - e * anomaly.sin() - mean_anomaly) / (1.0 - e * anomaly.cos());
+            anomaly -= step;
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+        anomaly
+    }
+
+    /// Position (metres) relative to the central body `time` Earth years after the epoch
+    pub fn position_at(&self, time: f64) -> Position {
+        let anomaly = self.eccentric_anomaly(self.mean_anomaly_at(time));
+        let e = self.eccentricity;
+        let a = self.semi_major_axis * AU;
+
+        // Coordinates in the orbital plane, periapsis along the first axis
+        let p = a * (anomaly.cos() - e);
+        let q = a * (1.0 - e * e).sqrt() * anomaly.sin();
+        let [x, y, z] = self.rotate_into_system(p, q);
+        Position { x, y, z }
+    }
+
+    /// Velocity (m/s) relative to the central body `time` Earth years after the epoch
+    pub fn velocity_at(&self, time: f64) -> Velocity {
+        let anomaly = self.eccentric_anomaly(self.mean_anomaly_at(time));
+        let e = self.eccentricity;
+        let a = self.semi_major_axis * AU;
+
+        // Rate of change of the eccentric anomaly, from Kepler's equation
+        let mean_motion = 2.0 * PI / (self.period * seconds_per_year());
+        let anomaly_rate = mean_motion / (1.0 - e * anomaly.cos());
+
+        let p = -a * anomaly.sin() * anomaly_rate;
+        let q = a * (1.0 - e * e).sqrt() * anomaly.cos() * anomaly_rate;
+        let [x, y, z] = self.rotate_into_system(p, q);
+        Velocity { x, y, z }
+    }
+
+    /// Position (metres) relative to the central body at the epoch
+    pub fn position(&self) -> Position {
+        self.position_at(0.0)
+    }
+
+    /// Distance from the central body in AU at the epoch
+    pub fn distance(&self) -> f64 {
+        let anomaly = self.eccentric_anomaly(self.mean_anomaly_at_epoch);
+        self.semi_major_axis * (1.0 - self.eccentricity * anomaly.cos())
+    }
+
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    pub fn apoapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    /// Rotate in-plane components (periapsis along `p`) into the system frame
+    fn rotate_into_system(&self, p: f64, q: f64) -> [f64; 3] {
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_o, cos_o) = self.longitude_of_ascending_node.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        // Coordinates measured from the ascending node
+        let x = p * cos_w - q * sin_w;
+        let y = p * sin_w + q * cos_w;
+
+        [
+            x * cos_o - y * cos_i * sin_o,
+            x * sin_o + y * cos_i * cos_o,
+            y * sin_i,
+        ]
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length(p: Position) -> f64 {
+        (p.x * p.x + p.y * p.y + p.z * p.z).sqrt()
+    }
+
+    #[test]
+    fn test_kepler_third_law() {
+        assert!((Orbit::kepler_period(1.0, 1.0) - 1.0).abs() < 1e-12);
+        assert!((Orbit::kepler_period(5.2, 1.0) - 11.86).abs() < 0.01);
+        // A heavier star shortens the year
+        assert!(Orbit::kepler_period(1.0, 2.0) < 1.0);
+    }
+
+    #[test]
+    fn test_position_follows_elements() {
+        let circular = Orbit::new(2.0, 0.0, 0.3, 1.0, 0.5, 2.0, 1.0);
+        for step in 0..20 {
+            let r = length(circular.position_at(step as f64 * 0.37)) / AU;
+            assert!((r - 2.0).abs() < 1e-9);
+        }
+
+        let eccentric = Orbit::new(1.0, 0.5, 0.0, 0.0, 0.0, 0.0, 1.0);
+        assert!((length(eccentric.position()) / AU - eccentric.periapsis()).abs() < 1e-9);
+        let half_period = eccentric.position_at(eccentric.period / 2.0);
+        assert!((length(half_period) / AU - eccentric.apoapsis()).abs() < 1e-9);
+
+        // Back where it started after one period
+        let later = eccentric.position_at(eccentric.period);
+        assert!((later.x - eccentric.position().x).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_kepler_equation_converges() {
+        for &e in &[0.0, 0.3, 0.9, 0.99] {
+            let orbit = Orbit::new(1.0, e, 0.0, 0.0, 0.0, 0.0, 1.0);
+            for step in 0..100 {
+                let m = step as f64 * 0.0628;
+                let anomaly = orbit.eccentric_anomaly(m);
+                assert!((anomaly - e * anomaly.sin() - m).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_velocity_matches_motion() {
+        let orbit = Orbit::new(1.5, 0.4, 0.2, 0.7, 1.9, 0.3, 1.0);
+        let time = 0.8;
+        let dt = 1e-6; // years
+
+        let before = orbit.position_at(time - dt);
+        let after = orbit.position_at(time + dt);
+        let velocity = orbit.velocity_at(time);
+        let seconds = 2.0 * dt * seconds_per_year();
+        assert!(((after.x - before.x) / seconds - velocity.x).abs() < 1.0);
+        assert!(((after.y - before.y) / seconds - velocity.y).abs() < 1.0);
+        assert!(((after.z - before.z) / seconds - velocity.z).abs() < 1.0);
+
+        // Vis-viva: v^2 = mu (2/r - 1/a)
+        let r = length(orbit.position_at(time));
+        let v_sq = velocity.x.powi(2) + velocity.y.powi(2) + velocity.z.powi(2);
+        let mu = G * SOLAR_MASS;
+        let expected = mu * (2.0 / r - 1.0 / (orbit.semi_major_axis * AU));
+        assert!((v_sq - expected).abs() / expected < 1e-3);
+    }
+
+    #[test]
+    fn test_state_vectors_round_trip() {
+        let position = Position { x: 1.2 * AU, y: -0.7 * AU, z: 0.1 * AU };
+        let velocity = Velocity { x: 9_000.0, y: 24_000.0, z: 1_500.0 };
+        let orbit = Orbit::from_state_vectors(position, velocity, 1.0);
+
+        assert!(orbit.eccentricity < 1.0);
+        let derived = orbit.position();
+        assert!((derived.x - position.x).abs() < 1.0);
+        assert!((derived.y - position.y).abs() < 1.0);
+        assert!((derived.z - position.z).abs() < 1.0);
+
+        let derived = orbit.velocity_at(0.0);
+        assert!((derived.x - velocity.x).abs() < 1.0);
+        assert!((derived.y - velocity.y).abs() < 1.0);
+        assert!((derived.z - velocity.z).abs() < 1.0);
+
+        // Orbits lying in the reference plane have no ascending node
+        let flat = Orbit::from_state_vectors(
+            Position { x: AU, y: 0.0, z: 0.0 },
+            Velocity { x: 0.0, y: 29_780.0, z: 0.0 },
+            1.0,
+        );
+        assert!(flat.inclination.abs() < 1e-12);
+        assert!((flat.semi_major_axis - 1.0).abs() < 0.01);
+        assert!((flat.position().x - AU).abs() < 1.0);
+    }
+}
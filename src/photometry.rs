@@ -0,0 +1,218 @@
+//! Star photometry: magnitudes, colour indices and display colours.
+
+use crate::solar_system::Star;
+
+pub const SOLAR_BOLOMETRIC_MAGNITUDE: f64 = 4.74;  // IAU 2015 B2
+
+/// Wavelength (m) below which starlight counts as ultraviolet: UV-B and shorter
+const ULTRAVIOLET_CUTOFF: f64 = 320e-9;
+
+/// Least fraction of a star's output emitted in the ultraviolet; the
+/// chromospheres of active cool dwarfs outshine their photospheres there
+const CHROMOSPHERIC_ULTRAVIOLET_FRACTION: f64 = 1e-3;
+
+/// Absolute bolometric magnitude of a luminosity (solar); dark objects are infinitely faint
+pub fn bolometric_magnitude(luminosity: f64) -> f64 {
+    if luminosity <= 0.0 {
+        return f64::INFINITY;
+    }
+    SOLAR_BOLOMETRIC_MAGNITUDE - 2.5 * luminosity.log10()
+}
+
+/// V-band bolometric correction for an effective temperature (K), from
+/// Flower (1996) with the coefficients corrected by Torres (2010)
+pub fn bolometric_correction(temperature: f64) -> f64 {
+    // The fits are only calibrated between late M dwarfs and early O stars
+    let log_t = temperature.clamp(2500.0, 50000.0).log10();
+    let coefficients: &[f64] = if log_t < 3.70 {
+        &[-0.190537291496456e5, 0.155144866764412e5, -0.421278819301717e4, 0.381476328422343e3]
+    } else if log_t < 3.90 {
+        &[-0.370510203809015e5, 0.385672629965804e5, -0.150651486316025e5,
+          0.261724637119416e4, -0.170623810323864e3]
+    } else {
+        &[-0.118115450538963e6, 0.137145973583929e6, -0.636233812100225e5,
+          0.147412923562646e5, -0.170587278406872e4, 0.788731721804990e2]
+    };
+    coefficients.iter().rev().fold(0.0, |bc, c| bc * log_t + c)
+}
+
+/// B–V colour index for an effective temperature (K), inverting Ballesteros (2012)
+pub fn color_index(temperature: f64) -> f64 {
+    // T = 4600 (1/(0.92 BV + 1.7) + 1/(0.92 BV + 0.62)) is a quadratic in 0.92 BV
+    let q = 4600.0 / temperature.max(1.0);
+    let b = 2.32 - 2.0 * q;
+    let c = 1.054 - 2.32 * q;
+    let x = (-b + (b * b - 4.0 * c).sqrt()) / 2.0;
+    x / 0.92
+}
+
+/// Distance modulus m − M for a distance in parsecs
+pub fn distance_modulus(distance_pc: f64) -> f64 {
+    5.0 * distance_pc.log10() - 5.0
+}
+
+/// Fraction of a blackbody's output emitted shortward of the ultraviolet
+/// cutoff, summing the series for the integrated Planck function
+pub fn ultraviolet_fraction(temperature: f64) -> f64 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+    const SECOND_RADIATION_CONSTANT: f64 = 1.438777e-2;  // hc/k in m·K
+    const PLANCK_NORMALISATION: f64 = 0.15398973382026504;  // 15/π⁴
+    let x = SECOND_RADIATION_CONSTANT / (ULTRAVIOLET_CUTOFF * temperature);
+    let sum: f64 = (1..=20).map(|n| {
+        let n = n as f64;
+        (-n * x).exp() / n * (x.powi(3) + 3.0 * x.powi(2) / n + 6.0 * x / n.powi(2) + 6.0 / n.powi(3))
+    }).sum();
+    (PLANCK_NORMALISATION * sum).min(1.0)
+}
+
+/// sRGB colour of a blackbody, normalised to full brightness. Uses the
+/// Planckian locus fit of Kim et al. (2002), valid from 1667 K to 25000 K;
+/// temperatures outside that range take the colour at its ends.
+pub fn blackbody_rgb(temperature: f64) -> [u8; 3] {
+    if temperature <= 0.0 {
+        return [0, 0, 0];
+    }
+    let t = temperature.clamp(1667.0, 25000.0);
+    let x = if t < 4000.0 {
+        -0.2661239e9 / t.powi(3) - 0.2343589e6 / t.powi(2) + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t.powi(3) + 2.1070379e6 / t.powi(2) + 0.2226347e3 / t + 0.240390
+    };
+    let y = if t < 2222.0 {
+        -1.1063814 * x.powi(3) - 1.34811020 * x.powi(2) + 2.18555832 * x - 0.20219683
+    } else if t < 4000.0 {
+        -0.9549476 * x.powi(3) - 1.37418593 * x.powi(2) + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x.powi(3) - 5.87338670 * x.powi(2) + 3.75112997 * x - 0.37001483
+    };
+
+    // Chromaticity to CIE XYZ at unit luminance, then to linear sRGB
+    let (cx, cy, cz) = (x / y, 1.0, (1.0 - x - y) / y);
+    let linear = [
+        3.2406 * cx - 1.5372 * cy - 0.4986 * cz,
+        -0.9689 * cx + 1.8758 * cy + 0.0415 * cz,
+        0.0557 * cx - 0.2040 * cy + 1.0570 * cz,
+    ];
+    let peak = linear.iter().cloned().fold(f64::MIN, f64::max);
+
+    linear.map(|c| {
+        let c = (c / peak).max(0.0);
+        let encoded = if c <= 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        (encoded * 255.0).round() as u8
+    })
+}
+
+impl Star {
+    /// Absolute bolometric magnitude
+    pub fn bolometric_magnitude(&self) -> f64 {
+        bolometric_magnitude(self.luminosity)
+    }
+
+    /// Absolute visual (V-band) magnitude
+    pub fn absolute_magnitude(&self) -> f64 {
+        self.bolometric_magnitude() - bolometric_correction(self.physical.surface_temperature)
+    }
+
+    /// B–V colour index
+    pub fn color_index(&self) -> f64 {
+        color_index(self.physical.surface_temperature)
+    }
+
+    /// sRGB display colour of the photosphere
+    pub fn rgb_color(&self) -> [u8; 3] {
+        blackbody_rgb(self.physical.surface_temperature)
+    }
+
+    /// Ultraviolet luminosity relative to the Sun's, from the photosphere or,
+    /// for cool stars, chromospheric activity
+    pub fn ultraviolet_luminosity(&self) -> f64 {
+        let fraction = ultraviolet_fraction(self.physical.surface_temperature).max(CHROMOSPHERIC_ULTRAVIOLET_FRACTION);
+        self.luminosity * fraction / ultraviolet_fraction(5772.0)
+    }
+
+    /// Apparent visual magnitude seen from a distance in parsecs through
+    /// `extinction` magnitudes of interstellar dust (A_V)
+    pub fn apparent_magnitude(&self, distance_pc: f64, extinction: f64) -> f64 {
+        self.absolute_magnitude() + distance_modulus(distance_pc) + extinction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generate, SolarSystem};
+
+    #[test]
+    fn test_solar_photometry() {
+        let mut sun = SolarSystem::generate_with_seed(0).star().clone();
+        sun.luminosity = 1.0;
+        sun.physical.surface_temperature = 5772.0;
+
+        assert!((sun.bolometric_magnitude() - 4.74).abs() < 1e-9);
+        assert!((sun.absolute_magnitude() - 4.81).abs() < 0.05);
+        assert!((sun.color_index() - 0.65).abs() < 0.05);
+
+        // The Sun seen from the Earth
+        let au_in_pc = 1.0 / 206264.806;
+        assert!((sun.apparent_magnitude(au_in_pc, 0.0) + 26.74).abs() < 0.1);
+
+        // At 10 pc apparent and absolute magnitude agree, and dust only dims
+        assert!((sun.apparent_magnitude(10.0, 0.0) - sun.absolute_magnitude()).abs() < 1e-9);
+        assert!((sun.apparent_magnitude(10.0, 1.5) - sun.absolute_magnitude() - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_colors_follow_temperature() {
+        // Vega defines B–V = 0; hot stars are bluer (negative), cool stars redder
+        assert!(color_index(9600.0).abs() < 0.1);
+        assert!(color_index(30000.0) < -0.2);
+        assert!(color_index(3200.0) > 1.4);
+        for t in [2000.0, 3000.0, 5000.0, 8000.0, 15000.0, 30000.0] {
+            assert!(color_index(t) > color_index(t * 1.1));
+        }
+
+        // The sRGB white point is D65, very nearly a 6500 K blackbody
+        let white = blackbody_rgb(6504.0);
+        assert!(white.iter().all(|&c| c > 245), "{:?}", white);
+
+        let [r, _, b] = blackbody_rgb(3000.0);
+        assert!(r == 255 && b < 150);
+        let [r, _, b] = blackbody_rgb(20000.0);
+        assert!(b == 255 && r < 220);
+
+        assert_eq!(blackbody_rgb(0.0), [0, 0, 0]);
+        assert!(bolometric_magnitude(0.0).is_infinite());
+    }
+
+    #[test]
+    fn test_ultraviolet_output() {
+        // A few percent of sunlight is shortward of 320 nm; hot stars emit most
+        // of their light there and M dwarfs almost none from the photosphere
+        assert!((ultraviolet_fraction(5772.0) - 0.045).abs() < 0.01);
+        assert!(ultraviolet_fraction(30000.0) > 0.5);
+        assert!(ultraviolet_fraction(3000.0) < 1e-3);
+        assert_eq!(ultraviolet_fraction(0.0), 0.0);
+
+        let mut star = SolarSystem::generate_with_seed(0).star().clone();
+        star.luminosity = 1.0;
+        star.physical.surface_temperature = 5772.0;
+        assert!((star.ultraviolet_luminosity() - 1.0).abs() < 1e-9);
+
+        // Flaring red dwarfs keep a floor of chromospheric ultraviolet
+        star.luminosity = 0.01;
+        star.physical.surface_temperature = 3000.0;
+        let ultraviolet = star.ultraviolet_luminosity();
+        assert!(ultraviolet > 1e-4 && ultraviolet < 0.01 * 0.1, "{}", ultraviolet);
+    }
+
+    #[test]
+    fn test_bolometric_corrections() {
+        // Hot and cool stars radiate much of their light outside the V band
+        assert!(bolometric_correction(5772.0).abs() < 0.15);
+        assert!(bolometric_correction(30000.0) < -2.5);
+        assert!(bolometric_correction(3000.0) < -1.5);
+        assert!(bolometric_correction(7000.0) > bolometric_correction(30000.0));
+    }
+}
@@ -1,172 +1,524 @@
-use crate::{Composition, Generate, PhysicalProperties, Position};
+use crate::{AtmosphericComposition, Composition, Generate, HabitabilityReport, HostConditions, Moon, Orbit, PhysicalProperties, PlanetHost, Position, RingSystem};
 use rand::prelude::*;
+use rand_distr::Normal;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 
-use crate::distributions::{random_planet_mass, random_orbital_period};
+use crate::seed::{object_id, SeedPath};
+use crate::moon::{tidal_locking_time, ROCK_RIGIDITY};
+use crate::orbit::{AU, SOLAR_MASS};
+use crate::atmosphere::{exosphere_temperature, retains_water, saturation_vapour_pressure};
+use crate::distributions::{
+    random_planet_mass_with_metallicity, random_planet_eccentricity, random_planet_inclination,
+    calculate_surface_temperature, equilibrium_temperature, greenhouse_optical_depth,
+    main_sequence_luminosity, snow_line,
+};
+
+/// Largest fraction of its host star's mass a planet can accumulate from the disk
+const MAX_PLANET_STAR_MASS_RATIO: f64 = 0.01;
+
+/// Instellation (Earth = 1) above which oceans boil away in a runaway greenhouse
+/// (Kopparapu et al. 2013), leaving no water to lock carbon dioxide into rock
+const RUNAWAY_GREENHOUSE_INSTELLATION: f64 = 1.1;
+
+/// Carbon dioxide held in an Earth-like crust, relative to the outgassed atmosphere;
+/// released it gives a Venus-like ~90 bar atmosphere
+const CRUSTAL_CARBON_DIOXIDE: f64 = 90.0;
+
+/// Nebular hydrogen and helium captured by a solid planet, as mole fractions
+/// over its outgassed atmosphere; only massive, cool worlds hold on to them
+const PRIMORDIAL_HYDROGEN: f64 = 0.05;
+const PRIMORDIAL_HELIUM: f64 = 0.01;
+
+/// Relative humidity of water-bearing worlds, setting vapour from the saturation pressure
+const RELATIVE_HUMIDITY: f64 = 0.5;
+
+/// Surface pressure (bar) of the atmosphere a planet of `mass` Earth masses
+/// outgasses and holds; small worlds lose most of theirs to space
+fn outgassed_pressure(planet_type: &PlanetType, mass: f64) -> f64 {
+    if planet_type.is_giant() {
+        mass.powi(2)
+    } else {
+        mass.powf(1.5) * (mass / 0.3).powi(2).min(1.0)
+    }
+}
+
+/// Instellation (Earth = 1) above which a gas giant's atmosphere puffs up into a hot Jupiter
+const HOT_JUPITER_INSTELLATION: f64 = 100.0;
+
+/// Instellation above which photoevaporation strips a mini-Neptune's thin
+/// envelope, leaving a bare super-Earth (the Fulton radius gap)
+const ENVELOPE_STRIPPING_INSTELLATION: f64 = 150.0;
+
+/// Instellation above which Neptunes and sub-Saturn giants lose their whole
+/// envelope, leaving a chthonian core
+const CHTHONIAN_INSTELLATION: f64 = 1000.0;
+
+/// Mass (Earth masses) of the rock and iron core a giant leaves behind once its
+/// envelope and ices have boiled away; Jupiter's and Saturn's are ~10
+const CHTHONIAN_CORE_MASS: f64 = 10.0;
+
+/// Surface temperature (K) above which silicate crust melts
+const LAVA_TEMPERATURE: f64 = 1200.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum PlanetType {
-    Terrestrial,
+    Terrestrial,      // Earth-like rocky world with some surface water
     GasGiant,
     IceGiant,
+    SuperEarth,       // rocky, 2-10 Earth masses
+    MiniNeptune,      // rocky core under a thick hydrogen envelope
+    HotJupiter,       // gas giant inflated by its star's heat
+    LavaWorld,        // crust molten by stellar heating
+    OceanWorld,       // global ocean over a water-rich interior
+    DesertWorld,      // dry rocky surface
+    IceWorld,         // frozen "ice ball" surface
+    ChthonianPlanet,  // core of a giant whose envelope boiled away
+    CarbonPlanet,     // carbides and graphite from a carbon-rich disk
+    DwarfPlanet,      // too small to clear its orbit
+}
+
+impl PlanetType {
+    /// Whether the planet is mostly a hydrogen and helium envelope rather than solid
+    pub fn is_giant(&self) -> bool {
+        matches!(self, PlanetType::GasGiant | PlanetType::HotJupiter | PlanetType::IceGiant | PlanetType::MiniNeptune)
+    }
+}
+
+/// Obliquity (degrees) of a planet, fixed by its seed. Giant impacts leave most
+/// spin axes within a few tens of degrees of upright; some are knocked onto
+/// their sides or over (Uranus, Venus)
+fn random_axial_tilt(seed: u64) -> f64 {
+    let mut rng = SeedPath::from_planet_seed(seed).stream("obliquity").rng();
+    if rng.gen::<f64>() < 0.1 {
+        rng.gen_range(0.0..180.0)
+    } else {
+        Normal::new(0.0f64, 25.0).unwrap().sample(&mut rng).abs().min(90.0)
+    }
+}
+
+/// Radius (m) of a planet of `mass` Earth masses. Giants follow the Chen &
+/// Kipping (2017) Neptunian and Jovian relations; solid worlds Zeng et al.
+/// (2016) for their interior's make-up
+fn planet_radius(planet_type: &PlanetType, mass: f64, water_fraction: f64) -> f64 {
+    let earth_radii = match planet_type {
+        PlanetType::DwarfPlanet => (mass * 5.51 / 2.0).cbrt(),  // rock and ice at ~2 g/cm³
+        // An envelope of a percent or so of the mass adds about an Earth
+        // radius to the core (Lopez & Fortney 2014)
+        PlanetType::MiniNeptune => mass.powf(0.27) + 1.0,
+        PlanetType::IceGiant => 0.808 * mass.powf(0.589),
+        PlanetType::GasGiant | PlanetType::HotJupiter => {
+            let radius = if mass < 132.0 { 0.808 * mass.powf(0.589) } else { 17.7 * mass.powf(-0.044) };
+            if *planet_type == PlanetType::HotJupiter { radius * 1.3 } else { radius }
+        }
+        // These classes name the state of the surface, not the interior: all are
+        // Earth-like silicate and iron, swollen by however much low-density water
+        // they hold (half-water worlds are ~25% larger; Zeng et al. 2019)
+        PlanetType::Terrestrial | PlanetType::SuperEarth | PlanetType::DesertWorld
+            | PlanetType::OceanWorld | PlanetType::IceWorld => mass.powf(0.27) * (1.0 + 0.5 * water_fraction),
+        // Its water boiled off; a molten mantle expands by a few percent (Bower et al. 2019)
+        PlanetType::LavaWorld => 1.05 * mass.powf(0.27),
+        // Silicon carbide and graphite are lighter than silicates and pack less
+        // tightly under pressure (Seager et al. 2007)
+        PlanetType::CarbonPlanet => 1.1 * mass.powf(0.27),
+        // Bare rock and iron, the water and ices gone with the envelope
+        PlanetType::ChthonianPlanet => mass.powf(0.27),
+    };
+    earth_radii * 6.371e6
+}
+
+fn planet_composition(planet_type: &PlanetType) -> Composition {
+    let (hydrogen, helium, metallicity, other) = match planet_type {
+        PlanetType::GasGiant | PlanetType::HotJupiter => (0.75, 0.24, 0.01, 0.0),
+        PlanetType::IceGiant => (0.20, 0.15, 0.15, 0.50),  // ice and volatiles
+        PlanetType::MiniNeptune => (0.015, 0.005, 0.8, 0.18),  // a few percent of gas over rock and ice
+        PlanetType::OceanWorld => (0.0, 0.0, 0.5, 0.5),  // water
+        PlanetType::IceWorld | PlanetType::DwarfPlanet => (0.0, 0.0, 0.6, 0.4),  // ice
+        PlanetType::CarbonPlanet => (0.0, 0.0, 0.5, 0.5),  // carbides and graphite
+        PlanetType::LavaWorld | PlanetType::ChthonianPlanet => (0.0, 0.0, 0.97, 0.03),
+        // One silicate and iron make-up, whatever the planet's mass or how dry
+        // its surface; surface water is a trace of the bulk
+        PlanetType::Terrestrial | PlanetType::SuperEarth | PlanetType::DesertWorld => (0.0, 0.0, 0.9, 0.1),
+    };
+    Composition { hydrogen, helium, metallicity, other }
+}
+
+/// Hydrogen envelope of a giant planet whose upper layers sit near `temperature` K
+fn giant_atmosphere(planet_type: &PlanetType, pressure: f64, temperature: f64) -> Atmosphere {
+    let ice_rich = matches!(planet_type, PlanetType::IceGiant | PlanetType::MiniNeptune);
+    let composition = AtmosphericComposition::envelope(ice_rich, temperature);
+    Atmosphere {
+        pressure,
+        composition,
+        optical_depth: greenhouse_optical_depth(composition.greenhouse_fraction(), 1.0),
+    }
+}
+
+/// Secondary atmosphere of a solid planet of the given composition
+fn rocky_atmosphere(pressure: f64, composition: AtmosphericComposition) -> Atmosphere {
+    Atmosphere {
+        pressure,
+        composition,
+        optical_depth: greenhouse_optical_depth(composition.greenhouse_fraction() * pressure, pressure),
+    }
+}
+
+/// A dry atmosphere with water vapour added over oceans or ice; vapour and the
+/// temperature it raises warm each other towards saturation
+fn humid_atmosphere(dry: &Atmosphere, equilibrium_temperature: f64) -> Atmosphere {
+    let vapour = AtmosphericComposition { water: 1.0, ..Default::default() };
+    let mut atmosphere = dry.clone();
+    for _ in 0..4 {
+        let temperature = equilibrium_temperature * (1.0 + 0.75 * atmosphere.optical_depth).powf(0.25);
+        let humidity = RELATIVE_HUMIDITY * saturation_vapour_pressure(temperature) / dry.pressure;
+        atmosphere = rocky_atmosphere(dry.pressure, dry.composition.mixed(&vapour, humidity.min(0.5)));
+    }
+    atmosphere
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Atmosphere {
     pub pressure: f64,  // in atmospheres
-    pub composition: Composition,
-    pub greenhouse_effect: f64,
+    pub composition: AtmosphericComposition,
+    pub optical_depth: f64,  // infrared optical depth behind the greenhouse effect
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Planet {
+    pub id: u64,
+    pub seed: u64,
     pub name: String,
     pub planet_type: PlanetType,
+    pub initial_type: PlanetType,  // bulk class at formation, before starlight reshapes it
+    pub initial_mass: f64,         // in Earth masses, at formation
     pub physical: PhysicalProperties,
-    pub position: Position,
-    pub orbital_period: f64,  // in Earth years
+    pub host: PlanetHost,     // the star or stars the orbit is around
+    pub orbit: Orbit,
     pub rotation_period: f64, // in Earth days
+    pub tidally_locked: bool, // set by `despin`; a locked planet turns once per orbit
+    pub axial_tilt: f64,      // in degrees, between the spin axis and the orbit normal
+    pub bond_albedo: f64,     // fraction of incident starlight reflected
+    pub water_fraction: f64,  // water mass fraction, oceans and ice included
     pub atmosphere: Option<Atmosphere>,
     pub composition: Composition,
+    pub moons: Vec<Moon>,     // added by `generate_moons` once the star is known
+    pub rings: Option<RingSystem>,
     pub habitable: bool,
+    pub habitability: Option<HabitabilityReport>,  // set by `assess_habitability`
 }
 
 impl Planet {
+    /// Position (m) from the host at the epoch
+    pub fn position(&self) -> Position {
+        self.orbit.position()
+    }
+
+    /// Orbital period in Earth years
+    pub fn orbital_period(&self) -> f64 {
+        self.orbit.period
+    }
+
     pub fn generate_at_distance(seed: u64, distance: f64) -> Self {
+        Self::generate_around_star(seed, distance, 1.0, 0.0)
+    }
+
+    /// Generate a planet orbiting `distance` AU (semi-major axis) from a star of
+    /// `star_mass` solar masses and `metallicity` [Fe/H]; metal-rich disks build
+    /// giant planets more readily
+    pub fn generate_around_star(seed: u64, distance: f64, star_mass: f64, metallicity: f64) -> Self {
         let mut rng = StdRng::seed_from_u64(seed);
         
-        // Generate basic properties
-        let mass = random_planet_mass(&mut rng, distance);
-        let orbital_period = random_orbital_period(&mut rng);
+        // Generate basic properties, limited by the mass of the host's disk
+        let max_mass = MAX_PLANET_STAR_MASS_RATIO * star_mass * 1.989e30 / 5.972e24;
+        let mass = random_planet_mass_with_metallicity(&mut rng, distance, metallicity).min(max_mass);
+
+        let orbit = Orbit::new(
+            distance,
+            random_planet_eccentricity(&mut rng),
+            random_planet_inclination(&mut rng),
+            rng.gen_range(0.0..2.0 * PI),
+            rng.gen_range(0.0..2.0 * PI),
+            rng.gen_range(0.0..2.0 * PI),
+            star_mass,
+        );
         
-        // Determine planet type based on mass and distance
+        // Water condenses beyond the snow line of the young star's disk; closer
+        // in planets are dry, with the odd water-rich world that migrated inward
+        let beyond_snow_line = distance > snow_line(main_sequence_luminosity(star_mass));
+        let water_fraction = if beyond_snow_line {
+            rng.gen_range(0.05..0.5)
+        } else {
+            10f64.powf(rng.gen_range(-6.0..-1.3))
+        };
+
+        // Bulk type from mass and distance; surface conditions refine it once
+        // the star is known (see `set_climate`)
         let planet_type = match (mass, distance) {
-            (m, d) if m < 2.0 && d < 4.0 => PlanetType::Terrestrial,
+            (m, _) if m < 0.1 => PlanetType::DwarfPlanet,
+            (m, _) if m < 2.0 => PlanetType::Terrestrial,
+            // Cores beyond the snow line grow fast enough to hold on to nebular gas
+            (m, _) if m < 10.0 => {
+                let envelope_probability = if beyond_snow_line { 0.9 } else { ((m - 2.0) / 6.0).clamp(0.0, 1.0) };
+                if rng.gen::<f64>() < envelope_probability { PlanetType::MiniNeptune } else { PlanetType::SuperEarth }
+            }
             (m, d) if m < 50.0 && d > 2.0 => PlanetType::IceGiant,
             _ => PlanetType::GasGiant,
         };
-
-        // Calculate radius based on mass and type
-        let radius = match planet_type {
-            PlanetType::Terrestrial => (mass / 5.51).powf(1.0/3.0) * 6.371e6, // Earth density
-            PlanetType::GasGiant => (mass / 1.33).powf(1.0/3.0) * 6.371e6,    // Jupiter density
-            PlanetType::IceGiant => (mass / 1.64).powf(1.0/3.0) * 6.371e6,    // Neptune density
+        // Disks with a high carbon-to-oxygen ratio build carbide rather than silicate worlds
+        let planet_type = match planet_type {
+            PlanetType::Terrestrial | PlanetType::SuperEarth
+                if rng.gen::<f64>() < (0.03 * 10f64.powf(metallicity)).min(0.15) => PlanetType::CarbonPlanet,
+            planet_type => planet_type,
         };
 
         let physical = PhysicalProperties {
             mass: mass * 5.972e24, // Convert to kg (Earth mass)
-            radius,
+            radius: planet_radius(&planet_type, mass, water_fraction),
             surface_temperature: 288.0, // Will be adjusted based on position
             density: 0.0,  // Will be calculated
             surface_gravity: 0.0, // Will be calculated
             escape_velocity: 0.0, // Will be calculated
         };
 
-        // Initialize with placeholder position
-        let position = Position { x: 0.0, y: 0.0, z: 0.0 };
-
-        let composition = match planet_type {
-            PlanetType::Terrestrial => Composition {
-                hydrogen: 0.0,
-                helium: 0.0,
-                metallicity: 0.9,
-                other: 0.1,
-            },
-            PlanetType::GasGiant => Composition {
-                hydrogen: 0.75,
-                helium: 0.24,
-                metallicity: 0.01,
-                other: 0.0,
-            },
-            PlanetType::IceGiant => Composition {
-                hydrogen: 0.20,
-                helium: 0.15,
-                metallicity: 0.15,
-                other: 0.50, // ice and volatiles
-            },
-        };
+        let composition = planet_composition(&planet_type);
 
-        // Atmosphere more likely for larger planets and at appropriate distances
+        // Atmosphere more likely for larger planets and at appropriate distances.
+        // Until the star is known assume temperate conditions; `set_climate`
+        // settles the atmosphere once it is
         let atmosphere = match planet_type {
-            PlanetType::Terrestrial if mass > 0.1 && mass < 5.0 => {
-                let greenhouse = if distance < 2.0 { 1.2 } else { 1.0 };
-                Some(Atmosphere {
-                    pressure: mass.powf(1.5),
-                    composition: Composition {
-                        hydrogen: 0.0,
-                        helium: 0.0,
-                        metallicity: 0.01,
-                        other: 0.99,
-                    },
-                    greenhouse_effect: greenhouse,
-                })
-            },
-            PlanetType::GasGiant | PlanetType::IceGiant => Some(Atmosphere {
-                pressure: mass.powf(2.0),
-                composition: composition.clone(),
-                greenhouse_effect: 1.5,
-            }),
+            PlanetType::DwarfPlanet => None,
+            ref t if t.is_giant() => Some(giant_atmosphere(t, outgassed_pressure(t, mass), 288.0)),
+            _ if mass > 0.1 && mass < 10.0 => {
+                Some(rocky_atmosphere(outgassed_pressure(&planet_type, mass), AtmosphericComposition::temperate()))
+            }
             _ => None,
         };
 
         let mut planet = Planet {
-            name: format!("Planet-{}", seed % 1000),
-            planet_type,
+            id: object_id(seed),
+            seed,
+            name: format!("Planet-{:016X}", object_id(seed)),
+            planet_type: planet_type.clone(),
+            initial_type: planet_type,
+            initial_mass: mass,
             physical,
-            position,
-            orbital_period,
+            host: PlanetHost::Star(0),
+            orbit,
             rotation_period: rng.gen_range(0.1..100.0),
+            tidally_locked: false,
+            axial_tilt: random_axial_tilt(seed),
+            bond_albedo: 0.0,
+            water_fraction,
             atmosphere,
             composition,
+            moons: Vec::new(),
+            rings: None,
             habitable: false,
+            habitability: None,
         };
 
         // Calculate derived properties
         planet.physical.density = planet.physical.calculate_density();
         planet.physical.surface_gravity = planet.physical.calculate_surface_gravity();
         planet.physical.escape_velocity = planet.physical.calculate_escape_velocity();
+        planet.bond_albedo = planet.albedo_for(&planet.atmosphere);
 
         planet
     }
 
-    /// Check if the planet could potentially support life
-    pub fn assess_habitability(&mut self, distance_from_star: f64, star_mass: f64) {
-        // First, set habitable to false by default
-        self.habitable = false;
+    /// Albedo of the bare surface or cloud tops, fixed by the planet's seed
+    fn surface_albedo(&self) -> f64 {
+        let mut rng = SeedPath::from_planet_seed(self.seed).stream("albedo").rng();
+        match self.planet_type {
+            PlanetType::Terrestrial | PlanetType::SuperEarth | PlanetType::DesertWorld => {
+                rng.gen_range(0.1..0.35)  // basalt to desert sand
+            }
+            PlanetType::OceanWorld => rng.gen_range(0.06..0.2),
+            PlanetType::IceWorld => rng.gen_range(0.5..0.8),
+            PlanetType::DwarfPlanet => rng.gen_range(0.1..0.7),  // dusty Ceres to frosted Pluto
+            PlanetType::LavaWorld | PlanetType::ChthonianPlanet => rng.gen_range(0.05..0.15),
+            PlanetType::CarbonPlanet => rng.gen_range(0.03..0.1),  // graphite and soot
+            PlanetType::GasGiant => rng.gen_range(0.3..0.38),     // Jupiter and Saturn ~0.34
+            PlanetType::HotJupiter => rng.gen_range(0.02..0.15),  // too hot for bright cloud decks
+            PlanetType::IceGiant | PlanetType::MiniNeptune => rng.gen_range(0.26..0.32),  // Uranus and Neptune ~0.29
+        }
+    }
+
+    /// Bond albedo under `atmosphere`, brightened on solid worlds by cloud
+    /// decks that thicken with surface pressure (Venus reflects ~3/4 of its light)
+    fn albedo_for(&self, atmosphere: &Option<Atmosphere>) -> f64 {
+        let surface = self.surface_albedo();
+        match atmosphere {
+            Some(atmosphere) if !self.planet_type.is_giant() => {
+                surface + (0.75 - surface) * (1.0 - (-atmosphere.pressure / 30.0).exp())
+            }
+            _ => surface,
+        }
+    }
+
+    /// Equilibrium temperature (K) under `luminosity` (solar) at `distance` AU
+    pub fn equilibrium_temperature(&self, luminosity: f64, distance: f64) -> f64 {
+        equilibrium_temperature(distance, luminosity, self.bond_albedo)
+    }
+
+    /// Settle the planet's type, atmosphere, albedo and surface temperature
+    /// under `luminosity` (solar) and `ultraviolet` (relative to the Sun's
+    /// ultraviolet output) at `distance` AU.
+    ///
+    /// Starlight inflates, strips or boils away hydrogen envelopes. Temperate
+    /// solid worlds keep their carbon dioxide in rock and are warmed mostly by
+    /// water vapour; strongly irradiated ones lose their oceans and release
+    /// it, and thin atmospheres that cannot hold oceans are left mostly carbon
+    /// dioxide. Ultraviolet heating of the upper atmosphere decides which light
+    /// gases escape. The resulting temperature and water content decide whether
+    /// a solid surface is molten, oceanic, dry or frozen.
+    ///
+    /// Everything is derived afresh from the planet's class and mass at
+    /// formation, so settling it again elsewhere undoes earlier changes.
+    pub fn set_climate<R: Rng + ?Sized>(&mut self, rng: &mut R, luminosity: f64, ultraviolet: f64, distance: f64) {
+        let instellation = luminosity / distance.powi(2);
+
+        self.planet_type = match self.initial_type {
+            PlanetType::GasGiant | PlanetType::IceGiant
+                if instellation > CHTHONIAN_INSTELLATION && self.initial_mass < 100.0 => PlanetType::ChthonianPlanet,
+            PlanetType::GasGiant if instellation > HOT_JUPITER_INSTELLATION => PlanetType::HotJupiter,
+            PlanetType::MiniNeptune if instellation > ENVELOPE_STRIPPING_INSTELLATION => PlanetType::SuperEarth,
+            ref planet_type => planet_type.clone(),
+        };
+        let mass = if self.planet_type == PlanetType::ChthonianPlanet {
+            self.initial_mass.min(CHTHONIAN_CORE_MASS)
+        } else {
+            self.initial_mass
+        };
+        self.physical.mass = mass * 5.972e24;
+        self.update_structure(mass);
+
+        let outgassed = outgassed_pressure(&self.planet_type, mass);
+        let mut humid = false;
+        self.atmosphere = match self.planet_type.clone() {
+            PlanetType::DwarfPlanet | PlanetType::ChthonianPlanet => None,
+            // Giants have no surface; temperatures are quoted at the 1 bar level
+            planet_type if planet_type.is_giant() => {
+                self.bond_albedo = self.albedo_for(&None);
+                let temperature = self.equilibrium_temperature(luminosity, distance);
+                Some(giant_atmosphere(&planet_type, outgassed, temperature))
+            }
+            _ if mass > 0.1 && mass < 10.0 => {
+                let runaway = instellation > RUNAWAY_GREENHOUSE_INSTELLATION;
+                let (pressure, mut composition) = if runaway {
+                    (outgassed * CRUSTAL_CARBON_DIOXIDE, AtmosphericComposition::runaway_greenhouse())
+                } else if outgassed < 0.1 {
+                    (outgassed, AtmosphericComposition::thin())
+                } else {
+                    (outgassed, AtmosphericComposition::temperate())
+                };
+                if self.planet_type == PlanetType::CarbonPlanet {
+                    let methane = AtmosphericComposition { methane: 1.0, ..Default::default() };
+                    composition = composition.mixed(&methane, 0.2);
+                }
+                let primordial = AtmosphericComposition {
+                    hydrogen: PRIMORDIAL_HYDROGEN,
+                    helium: PRIMORDIAL_HELIUM,
+                    ..Default::default()
+                };
+                let primordial_share = PRIMORDIAL_HYDROGEN + PRIMORDIAL_HELIUM;
+                composition = composition.mixed(&primordial.normalized(), primordial_share);
+
+                // Gases too light for the planet's gravity escape from the
+                // ultraviolet-heated exosphere, taking their pressure with them
+                self.bond_albedo = self.albedo_for(&Some(rocky_atmosphere(pressure, composition)));
+                let equilibrium = self.equilibrium_temperature(luminosity, distance);
+                let exosphere = exosphere_temperature(equilibrium, ultraviolet / distance.powi(2));
+                let retained = composition.escape(self.physical.escape_velocity, exosphere);
+                humid = !runaway && self.water_fraction > 1e-5 && retains_water(self.physical.escape_velocity, exosphere);
+                if retained > 0.0 {
+                    Some(rocky_atmosphere(pressure * retained / (1.0 - primordial_share), composition))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
 
-        // Only terrestrial planets can be habitable
-        if !matches!(self.planet_type, PlanetType::Terrestrial) {
-            return;
+        // Surface state follows the mean climate, before day-to-day variation
+        let solid_surface = matches!(
+            self.planet_type,
+            PlanetType::Terrestrial | PlanetType::SuperEarth | PlanetType::LavaWorld
+                | PlanetType::OceanWorld | PlanetType::DesertWorld | PlanetType::IceWorld
+                | PlanetType::CarbonPlanet
+        );
+        let carbon = self.initial_type == PlanetType::CarbonPlanet;
+        if solid_surface {
+            // Albedo, water vapour and the surface they warm feed back on each
+            // other; a second pass lets the surface class settle
+            let dry = self.atmosphere.clone();
+            for _ in 0..2 {
+                self.bond_albedo = self.albedo_for(&self.atmosphere);
+                let equilibrium = self.equilibrium_temperature(luminosity, distance);
+                if let (true, Some(dry)) = (humid, &dry) {
+                    self.atmosphere = Some(humid_atmosphere(dry, equilibrium));
+                }
+                let optical_depth = self.atmosphere.as_ref().map(|atm| atm.optical_depth).unwrap_or(0.0);
+                let temperature = equilibrium * (1.0 + 0.75 * optical_depth).powf(0.25);
+                let water = self.water_fraction;
+                self.planet_type = match temperature {
+                    t if t > LAVA_TEMPERATURE => PlanetType::LavaWorld,
+                    // Carbide crusts keep their character until they melt
+                    _ if carbon => PlanetType::CarbonPlanet,
+                    t if t < 240.0 && water > 1e-5 => PlanetType::IceWorld,
+                    t if t < 400.0 && water > 0.01 => PlanetType::OceanWorld,
+                    _ if mass >= 2.0 => PlanetType::SuperEarth,
+                    t if t > 373.0 || water < 1e-5 => PlanetType::DesertWorld,
+                    _ => PlanetType::Terrestrial,
+                };
+            }
+
+            // Magma oceans outgas sulfur dioxide and oxygen from vaporised rock
+            if let (PlanetType::LavaWorld, Some(atmosphere)) = (&self.planet_type, &mut self.atmosphere) {
+                let rock_vapour = AtmosphericComposition { sulfur_dioxide: 0.8, oxygen: 0.2, ..Default::default() };
+                *atmosphere = rocky_atmosphere(atmosphere.pressure, atmosphere.composition.mixed(&rock_vapour, 0.3));
+            }
         }
 
-        // Calculate habitable zone based on star mass
-        let inner_zone = 0.95 * star_mass.powf(0.5);
-        let outer_zone = 1.37 * star_mass.powf(0.5);
-        let in_habitable_zone = distance_from_star >= inner_zone && distance_from_star <= outer_zone;
+        self.update_structure(mass);
+        let optical_depth = self.atmosphere.as_ref().map(|atm| atm.optical_depth).unwrap_or(0.0);
+        self.bond_albedo = self.albedo_for(&self.atmosphere);
+        self.physical.surface_temperature =
+            calculate_surface_temperature(rng, distance, luminosity, self.bond_albedo, optical_depth);
+    }
 
-        // Check for conditions suitable for liquid water and Earth-like life
-        let has_atmosphere = self.atmosphere.is_some();
-        
-        // More lenient mass range (0.1 to 5 Earth masses)
-        let good_mass = self.physical.mass > 0.1 * 5.972e24 && self.physical.mass < 5.0 * 5.972e24;
-        
-        // Wider temperature range for potential life (250K to 400K)
-        let good_temp = self.physical.surface_temperature > 250.0 && self.physical.surface_temperature < 400.0;
-        
-        // More lenient gravity range (0.2 to 3.0 Earth gravities)
-        let good_gravity = self.physical.surface_gravity > 2.0 && self.physical.surface_gravity < 30.0;
-        
-        // Check for appropriate atmospheric pressure (0.1 to 10 Earth atmospheres)
-        let good_pressure = self.atmosphere.as_ref()
-            .map(|atm| atm.pressure >= 0.1 && atm.pressure <= 10.0)
-            .unwrap_or(false);
+    /// Bulk composition, radius and the properties that follow for the current type
+    fn update_structure(&mut self, mass: f64) {
+        self.composition = planet_composition(&self.planet_type);
+        self.physical.radius = planet_radius(&self.planet_type, mass, self.water_fraction);
+        self.physical.density = self.physical.calculate_density();
+        self.physical.surface_gravity = self.physical.calculate_surface_gravity();
+        self.physical.escape_velocity = self.physical.calculate_escape_velocity();
+    }
 
-        // Check for reasonable rotation period (0.1 to 100 Earth days)
-        let good_rotation = self.rotation_period >= 0.1 && self.rotation_period <= 100.0;
-        
-        // Only set to true if all conditions are met
-        self.habitable = has_atmosphere && good_mass && good_temp && good_gravity && 
-                        good_pressure && good_rotation && in_habitable_zone;
+    /// Let tides from a host of `star_mass` solar masses despin the planet over
+    /// `age` billion years, once its orbit is final; a locked planet keeps one
+    /// face to its star, so its day is its year
+    pub fn despin(&mut self, star_mass: f64, age: f64) {
+        let locking_time = tidal_locking_time(
+            self.orbit.semi_major_axis * AU,
+            self.physical.radius,
+            self.physical.mass,
+            star_mass * SOLAR_MASS,
+            ROCK_RIGIDITY,
+        );
+        self.tidally_locked = locking_time < age * 1e9;
+        if self.tidally_locked {
+            self.rotation_period = self.orbit.period * 365.25;
+        }
+    }
+
+    /// Score the planet's habitability under `host` and record whether any
+    /// factor rules out life (see `HabitabilityReport`)
+    pub fn assess_habitability(&mut self, host: &HostConditions) {
+        let report = self.habitability_report(host);
+        self.habitable = report.is_habitable();
+        self.habitability = Some(report);
     }
 }
 
@@ -190,7 +542,7 @@ mod tests {
         let planet = Planet::generate();
         assert!(planet.physical.mass > 0.0);
         assert!(planet.physical.radius > 0.0);
-        assert!(planet.orbital_period > 0.0);
+        assert!(planet.orbital_period() > 0.0);
     }
 
     #[test]
@@ -198,9 +550,10 @@ mod tests {
         let mut small_planet = Planet::generate_at_distance(1, 0.5); // Close to star, more likely terrestrial
         let mut giant_planet = Planet::generate_at_distance(999999, 5.0); // Far from star, more likely giant
         
-        // Test habitability assessment
-        small_planet.assess_habitability(0.5, 1.0);
-        giant_planet.assess_habitability(5.0, 1.0);
+        // Test habitability assessment around a Sun-like star
+        let sun = crate::tests::sun();
+        small_planet.assess_habitability(&sun);
+        giant_planet.assess_habitability(&sun);
         
         // Debug prints
         println!("Giant planet type: {:?}", giant_planet.planet_type);
@@ -213,5 +566,116 @@ mod tests {
         
         // Gas giants should never be habitable
         assert!(!giant_planet.habitable, "Gas/Ice giants should not be habitable");
+        let report = giant_planet.habitability.as_ref().unwrap();
+        assert!(report.failures.contains(&crate::HabitabilityFactor::Water));
+    }
+
+    #[test]
+    fn test_climates() {
+        let mut planet = crate::tests::earth_analogue(1.0, 1.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut climate = |mass: f64, luminosity: f64, distance: f64| {
+            planet.initial_mass = mass;
+            planet.set_climate(&mut rng, luminosity, luminosity, distance);
+            let atmosphere = planet.atmosphere.as_ref().unwrap();
+            let temperate = planet.equilibrium_temperature(luminosity, distance);
+            (atmosphere.pressure, planet.physical.surface_temperature, temperate, planet.bond_albedo, atmosphere.composition)
+        };
+
+        // Venus: runaway greenhouse under a bright cloud deck
+        let (pressure, surface, equilibrium, albedo, gas) = climate(0.815, 1.0, 0.723);
+        assert!(pressure > 30.0 && albedo > 0.6);
+        assert!(surface > 600.0 && equilibrium < 260.0, "{} {}", surface, equilibrium);
+        assert!(gas.carbon_dioxide > 0.9);
+
+        // Earth: a modest water vapour greenhouse in a nitrogen atmosphere that
+        // has lost its primordial hydrogen and helium
+        let (pressure, surface, equilibrium, _, gas) = climate(1.0, 1.0, 1.0);
+        assert!((pressure - 1.0).abs() < 1e-9);
+        assert!(surface > 265.0 && surface < 320.0 && surface > equilibrium, "{}", surface);
+        assert!(gas.nitrogen > 0.9 && gas.hydrogen == 0.0 && gas.helium == 0.0, "{:?}", gas);
+
+        // Mars: a thin, cold carbon dioxide atmosphere
+        let (pressure, surface, equilibrium, _, gas) = climate(0.107, 1.0, 1.524);
+        assert!(pressure < 0.02);
+        assert!(surface < 240.0 && surface < equilibrium * 1.1, "{}", surface);
+        assert!(gas.carbon_dioxide > 0.9 && gas.water == 0.0, "{:?}", gas);
+
+        // A cool super-Earth is massive enough to keep nebular hydrogen
+        let (_, _, _, _, gas) = climate(8.0, 1.0, 1.5);
+        assert!(gas.hydrogen > 0.01, "{:?}", gas);
+
+        // Settling the climate again gives the same world
+        let first = climate(0.815, 1.0, 0.723);
+        let second = climate(0.815, 1.0, 0.723);
+        assert_eq!(first.0, second.0);
+        assert_eq!(first.3, second.3);
+        assert_eq!(first.4, second.4);
+    }
+
+    #[test]
+    fn test_chthonian_cores() {
+        let mut cores = 0;
+        for seed in 0..300 {
+            let mut planet = Planet::generate_at_distance(seed, 5.0);
+            let giant = matches!(planet.planet_type, PlanetType::GasGiant | PlanetType::IceGiant);
+            if !giant || planet.physical.mass > 100.0 * 5.972e24 {
+                continue;
+            }
+            // Scorched at 0.02 AU from a Sun-like star, the envelope boils away
+            planet.set_climate(&mut StdRng::seed_from_u64(seed), 1.0, 1.0, 0.02);
+            assert_eq!(planet.planet_type, PlanetType::ChthonianPlanet);
+            assert!(planet.physical.mass <= CHTHONIAN_CORE_MASS * 5.972e24);
+            // Rock and iron, compressed no more than a massive super-Earth
+            assert!(planet.physical.density > 4000.0 && planet.physical.density < 10000.0,
+                "{}", planet.physical.density);
+
+            // Back where it formed, the planet is the giant it was born
+            planet.set_climate(&mut StdRng::seed_from_u64(seed), 1.0, 1.0, 5.0);
+            assert_eq!(planet.planet_type, planet.initial_type);
+            assert_eq!(planet.physical.mass, planet.initial_mass * 5.972e24);
+            assert!(planet.atmosphere.is_some());
+            cores += 1;
+        }
+        assert!(cores > 0);
+    }
+
+    #[test]
+    fn test_carbon_planets_melt() {
+        // A carbon super-Earth, massive enough to hold an atmosphere when scorched
+        let carbon = || {
+            let mut planet = crate::tests::first_planet("a carbon planet", 1.0, 0.5, |p| p.planet_type == PlanetType::CarbonPlanet);
+            planet.initial_mass = 3.0;
+            planet
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut temperate = carbon();
+        temperate.set_climate(&mut rng, 1.0, 1.0, 1.0);
+        assert_eq!(temperate.planet_type, PlanetType::CarbonPlanet);
+        assert!(temperate.atmosphere.unwrap().composition.methane > 0.0);
+
+        // Close to the star the crust melts and outgasses rock vapour
+        let mut scorched = carbon();
+        scorched.set_climate(&mut rng, 1.0, 1.0, 0.05);
+        assert_eq!(scorched.planet_type, PlanetType::LavaWorld);
+        let gas = scorched.atmosphere.as_ref().unwrap().composition;
+        assert!(gas.sulfur_dioxide > 0.1, "{:?}", gas);
+
+        // Settled further out again, the crust is carbide once more
+        scorched.set_climate(&mut rng, 1.0, 1.0, 1.0);
+        assert_eq!(scorched.planet_type, PlanetType::CarbonPlanet);
+    }
+
+    #[test]
+    fn test_orbit_sets_period_and_position() {
+        let planet = Planet::generate_around_star(7, 4.0, 0.5, 0.0);
+        assert_eq!(planet.orbit.semi_major_axis, 4.0);
+        assert!((planet.orbital_period() - (4.0f64.powi(3) / 0.5).sqrt()).abs() < 1e-9);
+
+        let distance = (planet.position().x.powi(2) + planet.position().y.powi(2)
+            + planet.position().z.powi(2)).sqrt() / 1.496e11;
+        assert!((distance - planet.orbit.distance()).abs() < 1e-9);
+        assert!(distance >= planet.orbit.periapsis() - 1e-9 && distance <= planet.orbit.apoapsis() + 1e-9);
     }
 }
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use rand::prelude::*;
+use crate::{MoonType, Planet, PlanetType};
+use crate::distributions::snow_line;
+use crate::moon::roche_limit;
+use crate::seed::SeedPath;
+
+const ICE_DENSITY: f64 = 900.0;    // kg/m³, ring particles
+const ROCK_DENSITY: f64 = 3000.0;  // kg/m³
+const PARTICLE_SIZE: f64 = 1.0;    // typical ring particle radius in metres
+const GAP_HILL_RADII: f64 = 4.0;   // width of a resonance gap in the moon's Hill radii
+
+/// Annulus emptied by a moon's resonance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGap {
+    pub inner_radius: f64,  // in meters from the planet's centre
+    pub outer_radius: f64,  // in meters
+    pub moon_id: u64,       // moon whose resonance clears the gap, the innermost one's if gaps merged
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingSystem {
+    pub inner_radius: f64,   // in meters from the planet's centre
+    pub outer_radius: f64,   // in meters, inside the Roche limit
+    pub optical_depth: f64,  // normal optical depth; above ~1 the ring is opaque
+    pub ice_fraction: f64,   // water ice by mass, the rest rock and dust
+    pub mass: f64,           // in kg
+    pub gaps: Vec<RingGap>,
+}
+
+impl RingSystem {
+    /// Whether a point at `radius` metres from the planet's centre lies in ring material
+    pub fn contains(&self, radius: f64) -> bool {
+        (self.inner_radius..=self.outer_radius).contains(&radius)
+            && !self.gaps.iter().any(|gap| (gap.inner_radius..=gap.outer_radius).contains(&radius))
+    }
+}
+
+/// Sort gaps outward and merge any that overlap into one
+fn merge_gaps(mut gaps: Vec<RingGap>) -> Vec<RingGap> {
+    gaps.sort_by(|a, b| a.inner_radius.partial_cmp(&b.inner_radius).unwrap());
+    let mut merged: Vec<RingGap> = Vec::with_capacity(gaps.len());
+    for gap in gaps {
+        match merged.last_mut() {
+            Some(last) if gap.inner_radius <= last.outer_radius => {
+                last.outer_radius = last.outer_radius.max(gap.outer_radius);
+            }
+            _ => merged.push(gap),
+        }
+    }
+    merged
+}
+
+impl Planet {
+    /// Chance that a planet of this type at its distance from a star of
+    /// `star_luminosity` holds a ring system
+    pub fn ring_probability(&self, star_luminosity: f64) -> f64 {
+        let base = match self.planet_type {
+            PlanetType::GasGiant | PlanetType::HotJupiter => 0.6,
+            PlanetType::IceGiant | PlanetType::MiniNeptune => 0.5,
+            _ => 0.02,
+        };
+        // Close to the star ice sublimates and rings disperse quickly
+        if self.orbit.semi_major_axis > snow_line(star_luminosity) { base } else { base * 0.3 }
+    }
+
+    /// Add a ring system, if the planet forms one. Call after `generate_moons`,
+    /// as moons clear gaps in the rings.
+    pub fn generate_rings(&mut self, star_luminosity: f64) {
+        let mut rng = SeedPath::from_planet_seed(self.seed).stream("rings").rng();
+        self.rings = None;
+
+        if rng.gen::<f64>() >= self.ring_probability(star_luminosity) {
+            return;
+        }
+
+        let ice_fraction = if self.orbit.semi_major_axis > snow_line(star_luminosity) {
+            rng.gen_range(0.9..0.99)
+        } else {
+            rng.gen_range(0.0..0.2)
+        };
+        let particle_density = ice_fraction * ICE_DENSITY + (1.0 - ice_fraction) * ROCK_DENSITY;
+
+        // Rings are debris that could not accrete into a moon inside the Roche limit
+        let roche = roche_limit(self.physical.radius, self.physical.density, particle_density);
+        let inner_radius = self.physical.radius * rng.gen_range(1.1..1.5);
+        let outer_radius = roche * rng.gen_range(0.6..1.0);
+        if outer_radius <= inner_radius * 1.05 {
+            return;
+        }
+
+        // Faint dusty rings to dense opaque ones
+        let optical_depth = 10f64.powf(rng.gen_range(-3.0..0.5));
+        let surface_density = optical_depth * 4.0 / 3.0 * particle_density * PARTICLE_SIZE;
+        let mass = surface_density * std::f64::consts::PI * (outer_radius.powi(2) - inner_radius.powi(2));
+
+        // Regular moons open gaps at their 2:1 and 3:2 inner resonances
+        let mut gaps = Vec::new();
+        for moon in self.moons.iter().filter(|m| m.moon_type == MoonType::Regular) {
+            let moon_distance = moon.orbit.semi_major_axis * 1.496e11;
+            // A few of the moon's Hill radii at the resonance, as a fraction of its radius
+            let width = (GAP_HILL_RADII * (moon.physical.mass / (3.0 * self.physical.mass)).cbrt()).min(0.5);
+            for ratio in [2.0f64, 1.5] {
+                let resonance = moon_distance * ratio.powf(-2.0 / 3.0);
+                if resonance > inner_radius && resonance < outer_radius {
+                    gaps.push(RingGap {
+                        inner_radius: resonance * (1.0 - width / 2.0),
+                        outer_radius: resonance * (1.0 + width / 2.0),
+                        moon_id: moon.id,
+                    });
+                }
+            }
+        }
+        let gaps = merge_gaps(gaps);
+
+        self.rings = Some(RingSystem {
+            inner_radius,
+            outer_radius,
+            optical_depth,
+            ice_fraction,
+            mass,
+            gaps,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::first_seed;
+
+    fn ringed_planet(seed: u64, distance: f64, luminosity: f64) -> Planet {
+        let mut planet = Planet::generate_at_distance(seed, distance);
+        planet.generate_moons(1.0, luminosity, 4.5);
+        planet.generate_rings(luminosity);
+        planet
+    }
+
+    #[test]
+    fn test_rings_inside_roche_limit() {
+        for seed in 0..300 {
+            let planet = ringed_planet(seed, 8.0, 1.0);
+            if let Some(rings) = &planet.rings {
+                let roche = roche_limit(planet.physical.radius, planet.physical.density, ICE_DENSITY);
+                assert!(rings.inner_radius > planet.physical.radius);
+                assert!(rings.outer_radius <= roche);
+                assert!(rings.optical_depth > 0.0 && rings.mass > 0.0);
+                assert!(rings.ice_fraction > 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_giants_are_ringed_more_often() {
+        let ring_fraction = |distance: f64, is_giant: bool| {
+            let planets: Vec<Planet> = (0..1000)
+                .map(|seed| ringed_planet(seed, distance, 1.0))
+                .filter(|p| p.planet_type.is_giant() == is_giant)
+                .collect();
+            planets.iter().filter(|p| p.rings.is_some()).count() as f64 / planets.len() as f64
+        };
+
+        let giants = ring_fraction(8.0, true);
+        assert!(giants > 0.3);
+        assert!(giants > ring_fraction(1.0, false));
+        // Hot giants rarely keep rings
+        assert!(ring_fraction(0.3, true) < giants);
+    }
+
+    #[test]
+    fn test_moons_clear_gaps() {
+        let planet = first_seed(
+            "a ring with gaps",
+            |seed| ringed_planet(seed, 8.0, 1.0),
+            |p| p.rings.as_ref().is_some_and(|r| !r.gaps.is_empty()),
+        );
+        let rings = planet.rings.as_ref().unwrap();
+        let gap = &rings.gaps[0];
+
+        let moon = planet.moons.iter().find(|m| m.id == gap.moon_id).unwrap();
+        let centre = (gap.inner_radius + gap.outer_radius) / 2.0;
+        let period_ratio = (moon.orbit.semi_major_axis * 1.496e11 / centre).powf(1.5);
+        assert!((period_ratio - 2.0).abs() < 1e-6 || (period_ratio - 1.5).abs() < 1e-6);
+
+        assert!(!rings.contains(centre));
+        assert!(!rings.contains(rings.outer_radius * 1.01));
+    }
+
+    #[test]
+    fn test_gaps_are_narrow_and_disjoint() {
+        for seed in 0..300 {
+            let planet = ringed_planet(seed, 8.0, 1.0);
+            if let Some(rings) = &planet.rings {
+                for gap in &rings.gaps {
+                    let width = (gap.outer_radius - gap.inner_radius) / gap.outer_radius;
+                    assert!(gap.inner_radius > 0.0 && width < 0.5);
+                }
+                assert!(rings.gaps.windows(2).all(|w| w[0].outer_radius < w[1].inner_radius));
+            }
+        }
+
+        let gap = |inner: f64, outer: f64, moon_id: u64| RingGap { inner_radius: inner, outer_radius: outer, moon_id };
+        let merged = merge_gaps(vec![gap(5.0, 6.0, 2), gap(1.0, 3.0, 1), gap(2.0, 4.0, 3)]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].inner_radius, merged[0].outer_radius, merged[0].moon_id), (1.0, 4.0, 1));
+        assert_eq!((merged[1].inner_radius, merged[1].outer_radius), (5.0, 6.0));
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use rand::prelude::*;
+use rand_distr::Poisson;
+use crate::{Galaxy, GalacticPosition, GalacticRegion, Position, SolarSystem};
+use crate::seed::SeedPath;
+
+/// Edge length of a catalogue sector in parsecs
+pub const SECTOR_SIZE: f64 = 10.0;
+
+/// Integer coordinates of a cubic sector of the galaxy
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SectorCoord {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl SectorCoord {
+    /// Sector containing the given position (parsecs)
+    pub fn containing(x: f64, y: f64, z: f64) -> Self {
+        SectorCoord {
+            x: (x / SECTOR_SIZE).floor() as i64,
+            y: (y / SECTOR_SIZE).floor() as i64,
+            z: (z / SECTOR_SIZE).floor() as i64,
+        }
+    }
+
+    /// Corner of the sector with the lowest coordinates (parsecs)
+    pub fn min_corner(&self) -> Position {
+        Position {
+            x: self.x as f64 * SECTOR_SIZE,
+            y: self.y as f64 * SECTOR_SIZE,
+            z: self.z as f64 * SECTOR_SIZE,
+        }
+    }
+
+    pub fn center(&self) -> Position {
+        let corner = self.min_corner();
+        Position {
+            x: corner.x + SECTOR_SIZE / 2.0,
+            y: corner.y + SECTOR_SIZE / 2.0,
+            z: corner.z + SECTOR_SIZE / 2.0,
+        }
+    }
+}
+
+/// Stable identifier of a catalogued star: its sector and index within that sector
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StarId {
+    pub sector: SectorCoord,
+    pub index: u32,
+}
+
+impl StarId {
+    /// Seed path of the star's system within a galaxy
+    pub fn path(&self, galaxy_seed: u64) -> SeedPath {
+        SeedPath::galaxy(galaxy_seed).sector(self.sector).star(self.index)
+    }
+}
+
+/// Lightweight catalogue entry that can be expanded into a full system on demand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StarStub {
+    pub id: StarId,
+    pub position: GalacticPosition,
+    pub seed: u64,
+}
+
+impl StarStub {
+    /// Galactic region the star lives in
+    pub fn region(&self, galaxy: &Galaxy) -> GalacticRegion {
+        galaxy.generate_region(self.position.x, self.position.y, self.position.z)
+    }
+
+    /// Generate the star's full system
+    pub fn expand(&self, galaxy: &Galaxy) -> SolarSystem {
+        SolarSystem::generate_in_region(&self.region(galaxy), self.seed)
+    }
+}
+
+impl Galaxy {
+    /// All stars in a sector, Poisson-sampled from the star density at its
+    /// centre; none lie beyond the halo's outer radius, where the galaxy ends
+    pub fn sector_stars(&self, sector: SectorCoord) -> Vec<StarStub> {
+        let center = sector.center();
+        let density = self.generate_region(center.x, center.y, center.z).star_density;
+        let expected = density * SECTOR_SIZE.powi(3);
+        if !(expected > 0.0 && expected.is_finite()) {
+            return Vec::new();
+        }
+
+        let mut rng = SeedPath::galaxy(self.seed).sector(sector).rng();
+        let count = Poisson::new(expected).unwrap().sample(&mut rng) as u32;
+        let corner = sector.min_corner();
+
+        (0..count)
+            .map(|index| {
+                let x = corner.x + rng.gen::<f64>() * SECTOR_SIZE;
+                let y = corner.y + rng.gen::<f64>() * SECTOR_SIZE;
+                let z = corner.z + rng.gen::<f64>() * SECTOR_SIZE;
+                let id = StarId { sector, index };
+                StarStub {
+                    id,
+                    position: GalacticPosition {
+                        x,
+                        y,
+                        z,
+                        r: (x * x + y * y).sqrt(),
+                        theta: y.atan2(x),
+                    },
+                    seed: id.path(self.seed).seed(),
+                }
+            })
+            .filter(|star| {
+                let p = &star.position;
+                (p.r * p.r + p.z * p.z).sqrt() <= self.halo.outer_radius
+            })
+            .collect()
+    }
+
+    /// Every star inside the axis-aligned box between `min` and `max` (parsecs)
+    pub fn stars_in_volume(&self, min: Position, max: Position) -> Vec<StarStub> {
+        let low = SectorCoord::containing(min.x, min.y, min.z);
+        let high = SectorCoord::containing(max.x, max.y, max.z);
+
+        let mut stars = Vec::new();
+        for x in low.x..=high.x {
+            for y in low.y..=high.y {
+                for z in low.z..=high.z {
+                    stars.extend(
+                        self.sector_stars(SectorCoord { x, y, z })
+                            .into_iter()
+                            .filter(|s| {
+                                let p = &s.position;
+                                (min.x..=max.x).contains(&p.x)
+                                    && (min.y..=max.y).contains(&p.y)
+                                    && (min.z..=max.z).contains(&p.z)
+                            }),
+                    );
+                }
+            }
+        }
+        stars
+    }
+
+    /// Look up a catalogued star by its identifier
+    pub fn star_by_id(&self, id: StarId) -> Option<StarStub> {
+        self.sector_stars(id.sector).into_iter().nth(id.index as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solar_neighbourhood(half_size: f64) -> (Position, Position) {
+        (
+            Position { x: 8000.0 - half_size, y: -half_size, z: -half_size },
+            Position { x: 8000.0 + half_size, y: half_size, z: half_size },
+        )
+    }
+
+    #[test]
+    fn test_stars_in_volume_deterministic() {
+        let galaxy = Galaxy::new();
+        let (min, max) = solar_neighbourhood(15.0);
+        let a = galaxy.stars_in_volume(min, max);
+        let b = galaxy.stars_in_volume(min, max);
+
+        assert!(!a.is_empty());
+        assert_eq!(a.len(), b.len());
+        for (s1, s2) in a.iter().zip(&b) {
+            assert_eq!(s1.id, s2.id);
+            assert_eq!(s1.seed, s2.seed);
+            assert_eq!(s1.position.x, s2.position.x);
+        }
+
+        for star in &a {
+            assert!(star.position.x >= min.x && star.position.x <= max.x);
+            assert!(star.position.y >= min.y && star.position.y <= max.y);
+            assert!(star.position.z >= min.z && star.position.z <= max.z);
+        }
+    }
+
+    #[test]
+    fn test_ids_independent_of_query() {
+        let galaxy = Galaxy::new();
+        let (min, max) = solar_neighbourhood(20.0);
+        let large = galaxy.stars_in_volume(min, max);
+        let (min, max) = solar_neighbourhood(5.0);
+        let small = galaxy.stars_in_volume(min, max);
+
+        for star in &small {
+            let same = large.iter().find(|s| s.id == star.id).expect("star missing from larger query");
+            assert_eq!(same.seed, star.seed);
+            assert_eq!(same.position.z, star.position.z);
+        }
+
+        let looked_up = galaxy.star_by_id(small[0].id).unwrap();
+        assert_eq!(looked_up.seed, small[0].seed);
+    }
+
+    #[test]
+    fn test_star_counts_follow_density() {
+        let galaxy = Galaxy::new();
+        let (min, max) = solar_neighbourhood(50.0);
+        let stars = galaxy.stars_in_volume(min, max);
+
+        let density = galaxy.generate_region(8000.0, 0.0, 0.0).star_density;
+        let expected = density * 100.0f64.powi(3);
+        let ratio = stars.len() as f64 / expected;
+        assert!(ratio > 0.7 && ratio < 1.3, "got {} stars, expected ~{:.0}", stars.len(), expected);
+
+        // Denser regions hold more stars
+        let inner = galaxy.stars_in_volume(
+            Position { x: 3950.0, y: -50.0, z: -50.0 },
+            Position { x: 4050.0, y: 50.0, z: 50.0 },
+        );
+        assert!(inner.len() > stars.len());
+    }
+
+    #[test]
+    fn test_stub_expands_to_system() {
+        let galaxy = Galaxy::new();
+        let (min, max) = solar_neighbourhood(10.0);
+        let stub = &galaxy.stars_in_volume(min, max)[0];
+
+        let system = stub.expand(&galaxy);
+        let again = stub.expand(&galaxy);
+        assert_eq!(system.star().stellar_type, again.star().stellar_type);
+        assert_eq!(system.planets.len(), again.planets.len());
+        assert_eq!(system.star().metallicity, stub.region(&galaxy).metallicity);
+    }
+}
@@ -0,0 +1,181 @@
+//! Hierarchical seed derivation.
+//!
+//! Every generated object is addressed by its path through the hierarchy
+//! galaxy → sector → star → planet → moon, with small bodies hanging off a
+//! star. Each step hashes the parent seed with the child's level and index,
+//! so siblings get independent random streams and the same path always
+//! yields the same seed and global ID.
+
+use serde::{Deserialize, Serialize};
+use rand::prelude::*;
+use crate::SectorCoord;
+
+/// Level of an object in the generation hierarchy
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum SeedLevel {
+    Galaxy,
+    Sector,
+    Star,
+    Planet,
+    Moon,
+    SmallBody,
+    Stream,    // named sub-stream of an object (e.g. its orbital layout)
+}
+
+impl SeedLevel {
+    fn tag(&self) -> u64 {
+        match self {
+            SeedLevel::Galaxy => 1,
+            SeedLevel::Sector => 2,
+            SeedLevel::Star => 3,
+            SeedLevel::Planet => 4,
+            SeedLevel::Moon => 5,
+            SeedLevel::SmallBody => 6,
+            SeedLevel::Stream => 7,
+        }
+    }
+}
+
+/// Tag mixed into a seed to derive the object's public ID
+const ID_TAG: u64 = 0x1D;
+
+/// SplitMix64 finaliser: a bijective scramble of all 64 bits
+fn scramble(value: u64) -> u64 {
+    let mut h = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+/// Mix a value into a running hash. The hash is scrambled before the value
+/// is folded in, so nearby (hash, value) pairs never cancel out
+pub(crate) fn mix(hash: u64, value: u64) -> u64 {
+    scramble(scramble(hash) ^ value)
+}
+
+/// Stable global ID of the object generated from `seed`
+pub fn object_id(seed: u64) -> u64 {
+    mix(seed, ID_TAG)
+}
+
+/// An object's address in the generation hierarchy, reduced to its seed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct SeedPath {
+    pub level: SeedLevel,
+    seed: u64,
+}
+
+impl SeedPath {
+    /// Root of a galaxy
+    pub fn galaxy(seed: u64) -> Self {
+        SeedPath { level: SeedLevel::Galaxy, seed: mix(seed, SeedLevel::Galaxy.tag()) }
+    }
+
+    /// A star system from a previously derived seed (e.g. `StarStub::seed`,
+    /// or a standalone `SolarSystem::generate_with_seed` seed)
+    pub fn from_seed(seed: u64) -> Self {
+        SeedPath { level: SeedLevel::Star, seed }
+    }
+
+    /// A planet from its previously derived seed (`Planet::seed`)
+    pub fn from_planet_seed(seed: u64) -> Self {
+        SeedPath { level: SeedLevel::Planet, seed }
+    }
+
+    fn child(&self, level: SeedLevel, index: u64) -> Self {
+        SeedPath { level, seed: mix(mix(self.seed, level.tag()), index) }
+    }
+
+    pub fn sector(&self, coord: SectorCoord) -> Self {
+        let path = self.child(SeedLevel::Sector, coord.x as u64);
+        SeedPath { seed: mix(mix(path.seed, coord.y as u64), coord.z as u64), ..path }
+    }
+
+    pub fn star(&self, index: u32) -> Self {
+        self.child(SeedLevel::Star, index as u64)
+    }
+
+    pub fn planet(&self, index: usize) -> Self {
+        self.child(SeedLevel::Planet, index as u64)
+    }
+
+    pub fn moon(&self, index: usize) -> Self {
+        self.child(SeedLevel::Moon, index as u64)
+    }
+
+    /// Small body keyed by its (quantised) position
+    pub fn small_body(&self, x: i64, y: i64, z: i64) -> Self {
+        let path = self.child(SeedLevel::SmallBody, x as u64);
+        SeedPath { seed: mix(mix(path.seed, y as u64), z as u64), ..path }
+    }
+
+    /// Independent named stream belonging to this object
+    pub fn stream(&self, name: &str) -> Self {
+        // FNV-1a keeps the name hash stable across platforms and releases
+        let name_hash = name.bytes().fold(0xCBF2_9CE4_8422_2325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        });
+        SeedPath { level: self.level, seed: mix(mix(self.seed, SeedLevel::Stream.tag()), name_hash) }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Stable global ID of the object at this path
+    pub fn id(&self) -> u64 {
+        object_id(self.seed)
+    }
+
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_paths_are_reproducible() {
+        let a = SeedPath::galaxy(7).sector(SectorCoord { x: 800, y: -1, z: 0 }).star(3).planet(2);
+        let b = SeedPath::galaxy(7).sector(SectorCoord { x: 800, y: -1, z: 0 }).star(3).planet(2);
+        assert_eq!(a, b);
+        assert_eq!(a.id(), b.id());
+        assert_eq!(a.level, SeedLevel::Planet);
+        assert_eq!(a.rng().gen::<u64>(), b.rng().gen::<u64>());
+    }
+
+    #[test]
+    fn test_neighbouring_systems_do_not_share_planets() {
+        // The old scheme seeded planet i of system s with s + i
+        let planets: HashSet<u64> = (0..1000u64)
+            .flat_map(|s| (0..12).map(move |i| SeedPath::from_seed(s).planet(i).seed()))
+            .collect();
+        assert_eq!(planets.len(), 12_000);
+
+        assert_ne!(SeedPath::from_seed(1).planet(0), SeedPath::from_seed(0).planet(1));
+    }
+
+    #[test]
+    fn test_ids_do_not_collide() {
+        let galaxy = SeedPath::galaxy(0);
+        let mut ids = HashSet::new();
+        for x in -10..10 {
+            for y in -10..10 {
+                let sector = galaxy.sector(SectorCoord { x, y, z: 0 });
+                for i in 0..50 {
+                    assert!(ids.insert(sector.star(i).id()));
+                }
+            }
+        }
+
+        // Levels, streams and transposed coordinates are all distinct
+        let star = SeedPath::from_seed(5);
+        assert_ne!(star.planet(0).seed(), star.moon(0).seed());
+        assert_ne!(star.stream("layout").seed(), star.stream("star").seed());
+        assert_ne!(star.small_body(1, 2, 3).seed(), star.small_body(3, 2, 1).seed());
+        assert_ne!(star.id(), star.seed());
+    }
+}
@@ -0,0 +1,324 @@
+//! The night sky as seen from a planet: catalogued stars of the surrounding
+//! galaxy plus the other stars and planets of the observer's own system.
+//!
+//! Sky coordinates are right ascension and declination in the system's
+//! reference frame, the frame planetary orbits are given in: declination is
+//! measured from its reference plane and right ascension from its x axis.
+//! Each system is tilted at random against the galactic plane.
+
+use std::f64::consts::PI;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    Galaxy, GalacticRegion, PlanetHost, Position, SolarSystem, Star, StarId,
+    StellarEnvironment,
+};
+use crate::photometry::blackbody_rgb;
+use crate::seed::SeedPath;
+
+/// Faintest apparent magnitude visible to the naked eye under a dark sky
+pub const NAKED_EYE_LIMIT: f64 = 6.5;
+
+/// Visual extinction by interstellar dust, ~1 magnitude per kiloparsec
+const EXTINCTION_PER_PARSEC: f64 = 1e-3;
+
+const PARSEC: f64 = 3.0857e16;  // meters
+
+/// What a point of light in the sky is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SkyObjectKind {
+    Star(StarId),  // a catalogued star of the galaxy
+    SystemStar(u64),  // another star of the observer's system, by id
+    Planet(u64),   // another planet of the observer's system, by id
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkyObject {
+    pub kind: SkyObjectKind,
+    pub right_ascension: f64,  // radians, 0 to 2π
+    pub declination: f64,      // radians, -π/2 to π/2
+    pub distance: f64,         // parsecs
+    pub apparent_magnitude: f64,
+    pub color: [u8; 3],        // sRGB
+}
+
+/// Everything brighter than a limiting magnitude, brightest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkyView {
+    pub time: f64,  // in Earth years after the system's epoch
+    pub limiting_magnitude: f64,
+    pub objects: Vec<SkyObject>,
+}
+
+fn sub(a: Position, b: Position) -> Position {
+    Position { x: a.x - b.x, y: a.y - b.y, z: a.z - b.z }
+}
+
+fn dot(a: Position, b: Position) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+fn cross(a: Position, b: Position) -> Position {
+    Position { x: a.y * b.z - a.z * b.y, y: a.z * b.x - a.x * b.z, z: a.x * b.y - a.y * b.x }
+}
+
+fn length(a: Position) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn scale(a: Position, s: f64) -> Position {
+    Position { x: a.x * s, y: a.y * s, z: a.z * s }
+}
+
+/// Right ascension and declination of a direction in the system frame
+fn sky_coordinates(direction: Position) -> (f64, f64) {
+    let r = length(direction);
+    let right_ascension = direction.y.atan2(direction.x).rem_euclid(2.0 * PI);
+    (right_ascension, (direction.z / r).clamp(-1.0, 1.0).asin())
+}
+
+/// Fraction of light a sphere reflects towards an observer at phase angle
+/// `alpha`, relative to full phase (Lambert)
+fn lambert_phase(alpha: f64) -> f64 {
+    (alpha.sin() + (PI - alpha) * alpha.cos()) / PI
+}
+
+/// Geometric albedo of a Lambert sphere with the given Bond albedo, whose phase integral is 3/2
+fn geometric_albedo(bond_albedo: f64) -> f64 {
+    bond_albedo / 1.5
+}
+
+impl SolarSystem {
+    /// Axes of the system's reference frame in galactic coordinates, a
+    /// uniformly random orientation fixed by the system's seed
+    pub fn galactic_orientation(&self) -> [Position; 3] {
+        let mut rng = SeedPath::from_seed(self.seed).stream("orientation").rng();
+        let cos_theta: f64 = rng.gen_range(-1.0..1.0);
+        let phi = rng.gen_range(0.0..2.0 * PI);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let pole = Position { x: sin_theta * phi.cos(), y: sin_theta * phi.sin(), z: cos_theta };
+
+        // Any vector in the plane, then turned by a random angle about the pole
+        let reference = if pole.z.abs() < 0.9 { Position { x: 0.0, y: 0.0, z: 1.0 } } else { Position { x: 1.0, y: 0.0, z: 0.0 } };
+        let u = cross(reference, pole);
+        let u = scale(u, 1.0 / length(u));
+        let v = cross(pole, u);
+        let psi = rng.gen_range(0.0..2.0 * PI);
+        let x_axis = Position {
+            x: u.x * psi.cos() + v.x * psi.sin(),
+            y: u.y * psi.cos() + v.y * psi.sin(),
+            z: u.z * psi.cos() + v.z * psi.sin(),
+        };
+        [x_axis, cross(pole, x_axis), pole]
+    }
+}
+
+impl Galaxy {
+    /// The sky from planet `planet_index` of `system`, which lies in `region`,
+    /// `time` Earth years after the epoch. Catalogued stars within `radius`
+    /// parsecs are considered, each shining with its system's primary star.
+    /// None when the system has no planet `planet_index`.
+    pub fn sky_view(
+        &self,
+        region: &GalacticRegion,
+        system: &SolarSystem,
+        planet_index: usize,
+        time: f64,
+        radius: f64,
+        limiting_magnitude: f64,
+    ) -> Option<SkyView> {
+        let host = system.planets.get(planet_index)?.host;
+        let axes = system.galactic_orientation();
+        let to_system_frame = |d: Position| Position { x: dot(d, axes[0]), y: dot(d, axes[1]), z: dot(d, axes[2]) };
+        let mut objects = Vec::new();
+
+        // Stars of the galaxy, skipping the observer's own catalogue entry
+        let center = Position { x: region.position.x, y: region.position.y, z: region.position.z };
+        for nearby in self.stars_within(center, radius) {
+            if nearby.star.seed == system.seed || nearby.distance < 1e-3 {
+                continue;
+            }
+            let environment = StellarEnvironment::from_region(&nearby.star.region(self));
+            let star = Star::generate_in_environment(nearby.star.seed, &environment);
+            let magnitude = star.apparent_magnitude(nearby.distance, EXTINCTION_PER_PARSEC * nearby.distance);
+            if magnitude > limiting_magnitude {
+                continue;
+            }
+
+            let p = &nearby.star.position;
+            let direction = sub(Position { x: p.x, y: p.y, z: p.z }, center);
+            let (right_ascension, declination) = sky_coordinates(to_system_frame(direction));
+            objects.push(SkyObject {
+                kind: SkyObjectKind::Star(nearby.star.id),
+                right_ascension,
+                declination,
+                distance: nearby.distance,
+                apparent_magnitude: magnitude,
+                color: star.rgb_color(),
+            });
+        }
+
+        // Companion stars the planet does not orbit
+        let state = system.state_at(time, &[]);
+        let observer = state.planets[planet_index].position;
+        for (i, (star, star_state)) in system.stars.iter().zip(&state.stars).enumerate() {
+            let is_host = match host {
+                PlanetHost::Star(h) => i == h,
+                PlanetHost::InnerBinary => i < 2,
+            };
+            if is_host {
+                continue;
+            }
+            let direction = sub(star_state.position, observer);
+            let distance = length(direction) / PARSEC;
+            let magnitude = star.apparent_magnitude(distance, 0.0);
+            if magnitude > limiting_magnitude {
+                continue;
+            }
+            let (right_ascension, declination) = sky_coordinates(direction);
+            objects.push(SkyObject {
+                kind: SkyObjectKind::SystemStar(star.id),
+                right_ascension,
+                declination,
+                distance,
+                apparent_magnitude: magnitude,
+                color: star.rgb_color(),
+            });
+        }
+
+        // Other planets, in the reflected light of every star of the system
+        for (j, (planet, planet_state)) in system.planets.iter().zip(&state.planets).enumerate() {
+            if j == planet_index {
+                continue;
+            }
+            let to_observer = sub(observer, planet_state.position);
+            let distance = length(to_observer);
+            let mut flux = 0.0;
+            let mut brightest = (0.0, &system.stars[0]);
+            for (star, star_state) in system.stars.iter().zip(&state.stars) {
+                let to_star = sub(star_state.position, planet_state.position);
+                let phase_angle = (dot(to_star, to_observer) / (length(to_star) * distance)).clamp(-1.0, 1.0).acos();
+                let reflected = geometric_albedo(planet.bond_albedo)
+                    * (planet.physical.radius / distance).powi(2)
+                    * lambert_phase(phase_angle);
+                let illumination = 10f64.powf(-0.4 * star.apparent_magnitude(length(to_star) / PARSEC, 0.0));
+                let contribution = illumination * reflected;
+                if contribution > brightest.0 {
+                    brightest = (contribution, star);
+                }
+                flux += contribution;
+            }
+
+            let magnitude = -2.5 * flux.log10();
+            if magnitude > limiting_magnitude {
+                continue;
+            }
+            let (right_ascension, declination) = sky_coordinates(scale(to_observer, -1.0));
+            objects.push(SkyObject {
+                kind: SkyObjectKind::Planet(planet.id),
+                right_ascension,
+                declination,
+                distance: distance / PARSEC,
+                apparent_magnitude: magnitude,
+                color: blackbody_rgb(brightest.1.physical.surface_temperature),
+            });
+        }
+
+        objects.sort_by(|a, b| a.apparent_magnitude.partial_cmp(&b.apparent_magnitude).unwrap());
+        Some(SkyView { time, limiting_magnitude, objects })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generate, StarStub};
+
+    /// A catalogued star near the Sun with at least three planets
+    fn observer_system(galaxy: &Galaxy) -> (StarStub, SolarSystem) {
+        galaxy
+            .stars_within(Position { x: 8000.0, y: 0.0, z: 0.0 }, 10.0)
+            .into_iter()
+            .map(|nearby| {
+                let system = nearby.star.expand(galaxy);
+                (nearby.star, system)
+            })
+            .find(|(_, system)| system.planets.len() >= 3)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sky_from_planet() {
+        let galaxy = Galaxy::new();
+        let (stub, system) = observer_system(&galaxy);
+        let region = stub.region(&galaxy);
+        let sky = galaxy.sky_view(&region, &system, 0, 0.0, 20.0, NAKED_EYE_LIMIT).unwrap();
+
+        assert!(sky.objects.iter().any(|o| matches!(o.kind, SkyObjectKind::Star(_))));
+        assert!(sky.objects.windows(2).all(|w| w[0].apparent_magnitude <= w[1].apparent_magnitude));
+        for object in &sky.objects {
+            assert!(object.apparent_magnitude <= NAKED_EYE_LIMIT);
+            assert!((0.0..2.0 * PI).contains(&object.right_ascension));
+            assert!(object.declination.abs() <= PI / 2.0);
+            assert_ne!(object.kind, SkyObjectKind::Star(stub.id));
+        }
+
+        // Catalogued stars shine as their expanded systems' primaries do
+        for object in sky.objects.iter().take(5) {
+            if let SkyObjectKind::Star(id) = object.kind {
+                let star = galaxy.star_by_id(id).unwrap().expand(&galaxy).star().clone();
+                let expected = star.apparent_magnitude(object.distance, EXTINCTION_PER_PARSEC * object.distance);
+                assert!((object.apparent_magnitude - expected).abs() < 1e-9);
+            }
+        }
+
+        let again = galaxy.sky_view(&region, &system, 0, 0.0, 20.0, NAKED_EYE_LIMIT).unwrap();
+        assert_eq!(sky.objects.len(), again.objects.len());
+
+        // There is no sky from a planet the system does not have
+        assert!(galaxy.sky_view(&region, &system, system.planets.len(), 0.0, 20.0, NAKED_EYE_LIMIT).is_none());
+    }
+
+    #[test]
+    fn test_system_bodies_in_sky() {
+        let galaxy = Galaxy::new();
+        let (stub, system) = observer_system(&galaxy);
+        let region = stub.region(&galaxy);
+
+        // With no magnitude limit every other planet and star of the system is listed
+        let deep = galaxy.sky_view(&region, &system, 0, 1.5, 5.0, 100.0).unwrap();
+        let planets = deep.objects.iter().filter(|o| matches!(o.kind, SkyObjectKind::Planet(_))).count();
+        let stars = deep.objects.iter().filter(|o| matches!(o.kind, SkyObjectKind::SystemStar(_))).count();
+        assert_eq!(planets, system.planets.len() - 1);
+        let hosts = match system.planets[0].host {
+            PlanetHost::Star(_) => 1,
+            PlanetHost::InnerBinary => 2,
+        };
+        assert_eq!(stars, system.stars.len() - hosts);
+        assert!(!deep.objects.iter().any(|o| o.kind == SkyObjectKind::Planet(system.planets[0].id)));
+
+        // Distant stars sit at the same place in every planet's sky
+        let other = galaxy.sky_view(&region, &system, 1, 1.5, 5.0, 100.0).unwrap();
+        for object in deep.objects.iter().filter(|o| matches!(o.kind, SkyObjectKind::Star(_))) {
+            let same = other.objects.iter().find(|o| o.kind == object.kind).unwrap();
+            assert!((same.right_ascension - object.right_ascension).abs() < 1e-6);
+            assert!((same.declination - object.declination).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_planet_phases() {
+        assert!((lambert_phase(0.0) - 1.0).abs() < 1e-12);
+        assert!(lambert_phase(PI).abs() < 1e-12);
+        assert!(lambert_phase(PI / 2.0) > 0.3 && lambert_phase(PI / 2.0) < 0.33);
+
+        let system = SolarSystem::generate_with_seed(7);
+        let axes = system.galactic_orientation();
+        for (i, a) in axes.iter().enumerate() {
+            for (j, b) in axes.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot(*a, *b) - expected).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use rand::prelude::*;
-use crate::{Composition, PhysicalProperties, Position, solar_system::StellarType};
+use crate::{Composition, Orbit, PhysicalProperties, Position, Velocity, solar_system::StellarType};
+use crate::orbit::{AU, G, SOLAR_MASS};
+use crate::seed::SeedPath;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SmallBodyType {
@@ -28,32 +30,45 @@ pub struct ElementDistribution {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmallBody {
+    pub id: u64,
     pub name: String,
     pub body_type: SmallBodyType,
     pub physical: PhysicalProperties,
-    pub position: Position,
+    pub orbit: Orbit,
     pub composition: Composition,
     pub elements: ElementDistribution,
-    pub orbital_period: f64,
     pub rotation_period: f64,
 }
 
 impl SmallBody {
-    /// Generate a small body at a specific position with deterministic properties
-    pub fn generate_at_position(system_seed: u64, position: Position, stellar_type: &StellarType, system_age: f64) -> Self {
-        // Use position components to create a deterministic seed
-        let x_seed = (position.x * 1e6) as i64;
-        let y_seed = (position.y * 1e6) as i64;
-        let z_seed = (position.z * 1e6) as i64;
-        let position_seed = (x_seed.wrapping_mul(73856093) ^ 
-                           y_seed.wrapping_mul(19349663) ^ 
-                           z_seed.wrapping_mul(83492791)) as u64;
-        let seed = system_seed.wrapping_add(position_seed);
+    /// Position (m) from the star at the epoch
+    pub fn position(&self) -> Position {
+        self.orbit.position()
+    }
+
+    /// Orbital period in Earth years
+    pub fn orbital_period(&self) -> f64 {
+        self.orbit.period
+    }
+
+    /// Generate a small body at a specific position with deterministic properties,
+    /// orbiting a star of `star_mass` solar masses
+    pub fn generate_at_position(
+        system_seed: u64,
+        position: Position,
+        stellar_type: &StellarType,
+        star_mass: f64,
+        _system_age: f64,
+    ) -> Self {
+        // Key the body by its position, quantised to millimetres
+        let quantise = |v: f64| (v * 1e3).round() as i64;
+        let path = SeedPath::from_seed(system_seed)
+            .small_body(quantise(position.x), quantise(position.y), quantise(position.z));
         
-        let mut rng = StdRng::seed_from_u64(seed);
+        let mut rng = path.rng();
         
         // Calculate distance from star
-        let distance = (position.x.powi(2) + position.y.powi(2) + position.z.powi(2)).sqrt() / 1.496e11;
+        let distance = (position.x.powi(2) + position.y.powi(2) + position.z.powi(2)).sqrt() / AU;
         
         // Determine body type based on distance and random factor
         let body_type = match distance {
@@ -210,6 +225,8 @@ impl SmallBody {
         physical.surface_gravity = physical.calculate_surface_gravity();
         physical.escape_velocity = physical.calculate_escape_velocity();
 
+        let orbit = Self::generate_orbit(&mut rng, position, &body_type, star_mass);
+
         let composition = Composition {
             hydrogen: 0.0,
             helium: 0.0,
@@ -218,16 +235,72 @@ impl SmallBody {
         };
 
         SmallBody {
-            name: format!("SB-{}", seed % 1000000),
+            id: path.id(),
+            name: format!("SB-{:016X}", path.id()),
             body_type,
             physical,
-            position,
+            orbit,
             composition,
             elements,
-            orbital_period: 0.0,  // Will be calculated by the system
             rotation_period: rng.gen_range(0.1..100.0),  // Hours
         }
     }
+
+    /// Orbit through `position` whose speed and tilt relative to a prograde
+    /// circular orbit suit the body type
+    fn generate_orbit<R: Rng + ?Sized>(
+        rng: &mut R,
+        position: Position,
+        body_type: &SmallBodyType,
+        star_mass: f64,
+    ) -> Orbit {
+        // Speed as a fraction of circular speed (escape is sqrt(2)), and maximum tilt
+        let (speed_range, max_tilt) = match body_type {
+            SmallBodyType::RockyAsteroid | SmallBodyType::MetallicAsteroid |
+            SmallBodyType::IcyAsteroid => (0.9..1.1, 0.3),
+            SmallBodyType::KuiperBeltObject => (0.85..1.15, 0.5),
+            SmallBodyType::Centaur => (0.7..1.25, 0.6),
+            SmallBodyType::ShortPeriodComet => (0.6..1.3, 0.5),
+            // Long-period comets spend most of their time near aphelion
+            SmallBodyType::LongPeriodComet => (0.1..0.5, std::f64::consts::PI),
+        };
+        let speed_factor = rng.gen_range(speed_range);
+        let tilt = rng.gen_range(-max_tilt..max_tilt);
+
+        // A body exactly on the star has no direction to orbit along; start it
+        // from the nearest quantised position instead
+        let position = if position.x == 0.0 && position.y == 0.0 && position.z == 0.0 {
+            Position { x: 1e-3, y: 0.0, z: 0.0 }
+        } else {
+            position
+        };
+        let r = (position.x.powi(2) + position.y.powi(2) + position.z.powi(2)).sqrt();
+        let radial = [position.x / r, position.y / r, position.z / r];
+
+        // Prograde direction around the system's pole, tilted about the radial axis
+        let mut prograde = [-radial[1], radial[0], 0.0];
+        let length = (prograde[0].powi(2) + prograde[1].powi(2)).sqrt();
+        if length < 1e-9 {
+            prograde = [1.0, 0.0, 0.0];
+        } else {
+            prograde = [prograde[0] / length, prograde[1] / length, 0.0];
+        }
+        let normal = [
+            radial[1] * prograde[2] - radial[2] * prograde[1],
+            radial[2] * prograde[0] - radial[0] * prograde[2],
+            radial[0] * prograde[1] - radial[1] * prograde[0],
+        ];
+
+        let speed = speed_factor * (G * star_mass * SOLAR_MASS / r).sqrt();
+        let (sin_t, cos_t) = tilt.sin_cos();
+        let velocity = Velocity {
+            x: speed * (cos_t * prograde[0] + sin_t * normal[0]),
+            y: speed * (cos_t * prograde[1] + sin_t * normal[1]),
+            z: speed * (cos_t * prograde[2] + sin_t * normal[2]),
+        };
+
+        Orbit::from_state_vectors(position, velocity, star_mass)
+    }
 }
 
 #[cfg(test)]
@@ -240,22 +313,46 @@ mod tests {
         let pos2 = Position { x: 1.0, y: 2.0, z: 3.0 };
         let pos3 = Position { x: 1.1, y: 2.0, z: 3.0 };
 
-        let body1 = SmallBody::generate_at_position(42, pos1, &StellarType::YellowDwarf, 4.5);
-        let body2 = SmallBody::generate_at_position(42, pos2, &StellarType::YellowDwarf, 4.5);
-        let body3 = SmallBody::generate_at_position(42, pos3, &StellarType::YellowDwarf, 4.5);
+        let body1 = SmallBody::generate_at_position(42, pos1, &StellarType::YellowDwarf, 1.0, 4.5);
+        let body2 = SmallBody::generate_at_position(42, pos2, &StellarType::YellowDwarf, 1.0, 4.5);
+        let body3 = SmallBody::generate_at_position(42, pos3, &StellarType::YellowDwarf, 1.0, 4.5);
 
         // Same position and seed should generate identical bodies
         assert_eq!(body1.body_type, body2.body_type);
         assert_eq!(body1.physical.mass, body2.physical.mass);
         
         // Different position should generate different body
-        assert_ne!(body1.body_type, body3.body_type);
+        assert_ne!(body1.id, body3.id);
+        assert_ne!(body1.physical.mass, body3.physical.mass);
+    }
+
+    #[test]
+    fn test_orbit_passes_through_position() {
+        let pos = Position { x: 2.1 * 1.496e11, y: -1.4 * 1.496e11, z: 0.05 * 1.496e11 };
+        let body = SmallBody::generate_at_position(42, pos, &StellarType::YellowDwarf, 1.0, 4.5);
+
+        assert!(body.orbit.eccentricity < 1.0);
+        assert!((body.position().x - pos.x).abs() < 1e3);
+        assert!((body.position().y - pos.y).abs() < 1e3);
+        assert!((body.position().z - pos.z).abs() < 1e3);
+        assert!((body.orbital_period() - body.orbit.semi_major_axis.powf(1.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_orbit_at_star_is_finite() {
+        let at_star = Position { x: 0.0, y: 0.0, z: 0.0 };
+        let body = SmallBody::generate_at_position(42, at_star, &StellarType::YellowDwarf, 1.0, 4.5);
+
+        let orbit = &body.orbit;
+        assert!(orbit.semi_major_axis.is_finite() && orbit.eccentricity.is_finite());
+        assert!(orbit.inclination.is_finite() && orbit.mean_anomaly_at_epoch.is_finite());
+        assert!(body.position().x.is_finite() && body.orbital_period().is_finite());
     }
 
     #[test]
     fn test_element_distribution() {
         let pos = Position { x: 2.0, y: 0.0, z: 0.0 };
-        let body = SmallBody::generate_at_position(42, pos, &StellarType::NeutronStar, 10.0);
+        let body = SmallBody::generate_at_position(42, pos, &StellarType::NeutronStar, 1.4, 10.0);
 
         // Check element ratios sum to approximately 1.0
         let total = body.elements.iron + body.elements.nickel + body.elements.gold + 
@@ -272,13 +369,13 @@ mod tests {
     fn test_distance_based_types() {
         // Inner system should favor rocky/metallic asteroids
         let inner_pos = Position { x: 1.496e11, y: 0.0, z: 0.0 }; // 1 AU
-        let inner_body = SmallBody::generate_at_position(42, inner_pos, &StellarType::YellowDwarf, 4.5);
+        let inner_body = SmallBody::generate_at_position(42, inner_pos, &StellarType::YellowDwarf, 1.0, 4.5);
         assert!(matches!(inner_body.body_type, 
             SmallBodyType::RockyAsteroid | SmallBodyType::MetallicAsteroid));
 
         // Outer system should favor icy bodies
         let outer_pos = Position { x: 30.0 * 1.496e11, y: 0.0, z: 0.0 }; // 30 AU
-        let outer_body = SmallBody::generate_at_position(42, outer_pos, &StellarType::YellowDwarf, 4.5);
+        let outer_body = SmallBody::generate_at_position(42, outer_pos, &StellarType::YellowDwarf, 1.0, 4.5);
         assert!(matches!(outer_body.body_type, 
             SmallBodyType::KuiperBeltObject | SmallBodyType::LongPeriodComet));
     }
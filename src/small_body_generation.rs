@@ -1,5 +1,6 @@
 use crate::{Position, small_bodies::SmallBody, solar_system::SolarSystem};
 use rand::prelude::*;
+use crate::seed::SeedPath;
 
 pub trait SmallBodyGeneration {
     fn generate_small_bodies(&self, region_center: Position, region_radius: f64, density: f64) -> Vec<SmallBody>;
@@ -8,11 +9,12 @@ pub trait SmallBodyGeneration {
 
 impl SmallBodyGeneration for SolarSystem {
     fn generate_small_bodies(&self, region_center: Position, region_radius: f64, density: f64) -> Vec<SmallBody> {
-        let mut rng = StdRng::seed_from_u64(
-            self.star.name.split('-').nth(1)
-                .and_then(|s| s.parse::<u64>().ok())
-                .unwrap_or(0)
-        );
+        // Each region of the system gets its own stream, keyed by its centre
+        let quantise = |v: f64| (v * 1e3).round() as i64;
+        let mut rng = SeedPath::from_seed(self.seed)
+            .stream("small-body-field")
+            .small_body(quantise(region_center.x), quantise(region_center.y), quantise(region_center.z))
+            .rng();
 
         // Convert AU to meters
         let au_to_m = 1.496e11;
@@ -42,11 +44,10 @@ impl SmallBodyGeneration for SolarSystem {
             };
 
             let body = SmallBody::generate_at_position(
-                self.star.name.split('-').nth(1)
-                    .and_then(|s| s.parse::<u64>().ok())
-                    .unwrap_or(0),
+                self.seed,
                 pos,
-                &self.star.stellar_type,
+                &self.star().stellar_type,
+                self.star().physical.mass / 1.989e30,
                 self.system_age
             );
 
@@ -55,12 +56,13 @@ impl SmallBodyGeneration for SolarSystem {
 
         // Sort by distance from center for deterministic ordering
         bodies.sort_by(|a, b| {
-            let dist_a = ((a.position.x - center_m.x).powi(2) + 
-                         (a.position.y - center_m.y).powi(2) + 
-                         (a.position.z - center_m.z).powi(2)).sqrt();
-            let dist_b = ((b.position.x - center_m.x).powi(2) + 
-                         (b.position.y - center_m.y).powi(2) + 
-                         (b.position.z - center_m.z).powi(2)).sqrt();
+            let (a, b) = (a.position(), b.position());
+            let dist_a = ((a.x - center_m.x).powi(2) + 
+                         (a.y - center_m.y).powi(2) + 
+                         (a.z - center_m.z).powi(2)).sqrt();
+            let dist_b = ((b.x - center_m.x).powi(2) + 
+                         (b.y - center_m.y).powi(2) + 
+                         (b.z - center_m.z).powi(2)).sqrt();
             dist_a.partial_cmp(&dist_b).unwrap()
         });
 
@@ -1,6 +1,13 @@
-use crate::{Composition, Generate, PhysicalProperties, Position};
-use crate::distributions::{habitable_zone_range, calculate_surface_temperature};
+use crate::{Composition, Generate, HostConditions, Orbit, PhysicalProperties, PlanetHost, Position, GalacticRegion, PopulationType};
+use crate::binary::{effective_luminosity, effective_ultraviolet, generate_companions, planet_zones, PlanetZone};
+use crate::distributions::{
+    habitable_zone_range, planet_occurrence_factor,
+    effective_temperature, InitialMassFunction,
+};
+use crate::evolution::{evolutionary_stage, evolve, EvolutionaryStage};
 use crate::planet::Planet;
+use crate::seed::SeedPath;
+use crate::spectral::SpectralClass;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -33,7 +40,7 @@ pub enum StellarType {
 }
 
 impl StellarType {
-    fn mass_range(&self) -> (f64, f64) {
+    pub(crate) fn mass_range(&self) -> (f64, f64) {
         match self {
             StellarType::BrownDwarf => (0.01, 0.08),
             StellarType::RedDwarf => (0.08, 0.45),
@@ -55,6 +62,58 @@ impl StellarType {
         }
     }
 
+    /// Main sequence type of a star of `mass` solar masses
+    pub(crate) fn main_sequence_for_mass(mass: f64) -> Self {
+        [
+            StellarType::BrownDwarf, StellarType::RedDwarf, StellarType::OrangeDwarf,
+            StellarType::YellowDwarf, StellarType::WhiteDwarf, StellarType::BlueDwarf,
+            StellarType::BlueGiant,
+        ]
+        .into_iter()
+        .find(|t| mass < t.mass_range().1)
+        .unwrap_or(StellarType::BlueSupergiant)
+    }
+
+    /// Hydrogen-burning (or failed, for brown dwarfs) dwarf stars
+    pub fn is_main_sequence(&self) -> bool {
+        matches!(self,
+            StellarType::BrownDwarf | StellarType::RedDwarf | StellarType::OrangeDwarf |
+            StellarType::YellowDwarf | StellarType::WhiteDwarf | StellarType::BlueDwarf |
+            StellarType::BlueGiant | StellarType::BlueSupergiant
+        )
+    }
+
+    /// Type of a star of `initial_mass` solar masses and [Fe/H] at `age` Gyr:
+    /// on the main sequence for its lifetime, a giant for roughly a tenth as long
+    /// again, then a remnant set by its initial mass
+    pub fn classify(initial_mass: f64, metallicity: f64, age: f64) -> Self {
+        match evolutionary_stage(initial_mass, metallicity, age) {
+            _ if initial_mass < 0.08 => StellarType::BrownDwarf,
+            EvolutionaryStage::MainSequence => Self::main_sequence_for_mass(initial_mass),
+            EvolutionaryStage::GiantBranch => match initial_mass {
+                m if m < 8.0 => StellarType::RedGiant,
+                m if m < 30.0 => StellarType::SuperGiant,
+                _ => StellarType::HyperGiant,
+            },
+            EvolutionaryStage::Remnant => match initial_mass {
+                m if m < 8.0 => StellarType::WhiteDwarfRemnant,
+                m if m < 25.0 => StellarType::NeutronStar,
+                _ => StellarType::BlackHole,
+            },
+        }
+    }
+
+    /// Draw a type that a star of the given age (Gyr) and [Fe/H] can have,
+    /// with the range of initial masses that give it
+    fn generate_for_age<R: Rng + ?Sized>(rng: &mut R, age: f64, metallicity: f64) -> (Self, (f64, f64)) {
+        loop {
+            let stellar_type = Self::generate_random(rng);
+            if let Some(window) = stellar_type.initial_mass_window(age, metallicity) {
+                return (stellar_type, window);
+            }
+        }
+    }
+
     fn generate_random<R: Rng + ?Sized>(rng: &mut R) -> Self {
         let roll = rng.gen::<f64>();
         match roll {
@@ -78,48 +137,13 @@ impl StellarType {
         }
     }
 
-    fn temperature_range(&self) -> (f64, f64) {
-        match self {
-            StellarType::BrownDwarf => (300.0, 2800.0),
-            StellarType::RedDwarf => (2800.0, 3500.0),
-            StellarType::OrangeDwarf => (3500.0, 5000.0),
-            StellarType::YellowDwarf => (5000.0, 6000.0),
-            StellarType::WhiteDwarf => (6000.0, 7500.0),
-            StellarType::BlueDwarf => (7500.0, 10000.0),
-            StellarType::BlueGiant => (10000.0, 30000.0),
-            StellarType::BlueSupergiant => (30000.0, 50000.0),
-            StellarType::RedGiant => (3000.0, 4500.0),
-            StellarType::SuperGiant => (3500.0, 8000.0),
-            StellarType::HyperGiant => (4000.0, 50000.0),
-            StellarType::WhiteDwarfRemnant => (4000.0, 150000.0),
-            StellarType::NeutronStar => (100000.0, 1000000.0),
-            StellarType::BlackHole => (0.0, 0.0),
-            StellarType::QuarkStar => (100000.0, 1000000.0),
-            StellarType::PulsarStar => (100000.0, 1000000.0),
-            StellarType::MagnetarStar => (100000.0, 1000000.0),
-        }
-    }
-
-    fn luminosity_factor(&self, mass: f64) -> f64 {
-        match self {
-            StellarType::BrownDwarf => mass.powf(2.0) * 0.001,
-            StellarType::RedDwarf => mass.powf(3.0) * 0.01,
-            StellarType::OrangeDwarf => mass.powf(3.5) * 0.1,
-            StellarType::YellowDwarf => mass.powf(3.5),
-            StellarType::WhiteDwarf => mass.powf(3.5) * 2.0,
-            StellarType::BlueDwarf => mass.powf(3.5) * 5.0,
-            StellarType::BlueGiant => mass.powf(3.8) * 10.0,
-            StellarType::BlueSupergiant => mass.powf(4.0) * 100.0,
-            StellarType::RedGiant => mass.powf(3.0) * 1000.0,
-            StellarType::SuperGiant => mass.powf(3.5) * 10000.0,
-            StellarType::HyperGiant => mass.powf(4.0) * 100000.0,
-            StellarType::WhiteDwarfRemnant => mass.powf(-3.0) * 0.0001,
-            StellarType::NeutronStar => mass.powf(-2.0) * 0.00001,
-            StellarType::BlackHole => 0.0,
-            StellarType::QuarkStar => mass.powf(-2.0) * 0.00001,
-            StellarType::PulsarStar => mass.powf(-2.0) * 0.00001,
-            StellarType::MagnetarStar => mass.powf(-2.0) * 0.00001,
-        }
+    /// Dead stars: white dwarfs, neutron stars and black holes, and the exotic
+    /// neutron star variants
+    pub fn is_remnant(&self) -> bool {
+        matches!(self,
+            StellarType::WhiteDwarfRemnant | StellarType::NeutronStar | StellarType::BlackHole |
+            StellarType::QuarkStar | StellarType::PulsarStar | StellarType::MagnetarStar
+        )
     }
 
     pub fn can_have_planets(&self) -> bool {
@@ -132,6 +156,15 @@ impl StellarType {
         )
     }
 
+    /// Distance (AU) of the innermost planet of a Titius-Bode sequence
+    fn base_orbit_distance(&self) -> f64 {
+        match self {
+            StellarType::BrownDwarf | StellarType::RedDwarf => 0.05,
+            StellarType::WhiteDwarfRemnant => 0.1,
+            _ => 0.3, // Increased from 0.2 to spread out planets
+        }
+    }
+
     fn planet_count_range(&self) -> (usize, usize) {
         match self {
             StellarType::BrownDwarf => (0, 3),
@@ -149,14 +182,18 @@ impl StellarType {
 // Star and SolarSystem implementations...
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Star {
+    pub id: u64,
     pub name: String,
     pub stellar_type: StellarType,
     pub physical: PhysicalProperties,
     pub composition: Composition,
+    pub metallicity: f64, // [Fe/H] relative to Sol
+    pub initial_mass: f64, // in solar masses, at birth
     pub luminosity: f64,  // relative to Sol
     pub age: f64,        // in billions of years
     pub magnetic_field: f64, // in Tesla
     pub rotation_period: f64, // in Earth days
+    pub spectral_class: Option<SpectralClass>, // None for neutron stars and black holes
 }
 
 impl Generate for Star {
@@ -165,32 +202,117 @@ impl Generate for Star {
     }
 
     fn generate_with_seed(seed: u64) -> Self {
-        let mut rng = StdRng::seed_from_u64(seed);
+        let mut rng = SeedPath::from_seed(seed).stream("star").rng();
         
+        // Field stars without a galactic context are taken to be solar metallicity
         let stellar_type = StellarType::generate_random(&mut rng);
-        let (min_mass, max_mass) = stellar_type.mass_range();
-        let (min_temp, max_temp) = stellar_type.temperature_range();
-        
-        let mass_solar = min_mass + rng.gen::<f64>() * (max_mass - min_mass);
-        let luminosity = stellar_type.luminosity_factor(mass_solar);
-        
-        let radius = match stellar_type {
-            StellarType::BlackHole => {
-                2.0 * 6.674e-11 * (mass_solar * 1.989e30) / (299_792_458.0f64.powi(2))
-            },
-            _ => {
-                match stellar_type {
-                    StellarType::RedGiant | StellarType::SuperGiant | StellarType::HyperGiant =>
-                        mass_solar.powf(0.5) * 100.0 * 6.957e8,
-                    StellarType::WhiteDwarfRemnant | StellarType::NeutronStar |
-                    StellarType::QuarkStar | StellarType::PulsarStar | StellarType::MagnetarStar =>
-                        mass_solar.powf(0.5) * 0.01 * 6.957e8,
-                    _ => mass_solar.powf(0.8) * 6.957e8,
-                }
+        let (min_mass, max_mass) = stellar_type.initial_mass_range();
+        let (initial_mass, age) = loop {
+            let initial_mass = min_mass + rng.gen::<f64>() * (max_mass - min_mass);
+            // Only some progenitors have reached the type's stage within the age of the universe
+            if let Some((youngest, oldest)) = stellar_type.age_window(initial_mass, 0.0) {
+                break (initial_mass, youngest + rng.gen::<f64>() * (oldest - youngest));
             }
         };
 
-        let temp = min_temp + rng.gen::<f64>() * (max_temp - min_temp);
+        Self::build(seed, &mut rng, stellar_type, initial_mass, age, 0.0)
+    }
+}
+
+impl Star {
+    /// Generate a field star whose mass is drawn from `imf` and whose type
+    /// follows from that mass and its age
+    pub fn generate_with_imf(seed: u64, imf: InitialMassFunction) -> Self {
+        let mut rng = SeedPath::from_seed(seed).stream("star").rng();
+        let initial_mass = imf.sample(&mut rng);
+        let age = rng.gen_range(0.1..13.8);
+        Self::from_initial_mass(seed, &mut rng, initial_mass, age, 0.0)
+    }
+
+    /// Build the star that a star born with `initial_mass` solar masses has
+    /// become by `age` Gyr
+    pub(crate) fn from_initial_mass<R: Rng + ?Sized>(
+        seed: u64,
+        rng: &mut R,
+        initial_mass: f64,
+        age: f64,
+        metallicity: f64,
+    ) -> Self {
+        let stellar_type = StellarType::classify(initial_mass, metallicity, age);
+        Self::build(seed, rng, stellar_type, initial_mass, age, metallicity)
+    }
+}
+
+/// Galactic birthplace of a star, setting its composition, age and type mix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StellarEnvironment {
+    pub population: PopulationType,
+    pub metallicity: f64,         // [Fe/H]
+    pub young_star_fraction: f64, // fraction of stars younger than ~1 Gyr
+    pub imf: Option<InitialMassFunction>, // None draws types from the calibrated frequency table
+}
+
+impl StellarEnvironment {
+    pub fn from_region(region: &GalacticRegion) -> Self {
+        StellarEnvironment {
+            population: region.population.clone(),
+            metallicity: region.metallicity,
+            young_star_fraction: region.young_star_fraction,
+            imf: None,
+        }
+    }
+
+    /// Draw a stellar age in billions of years
+    fn sample_age<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        if rng.gen::<f64>() < self.young_star_fraction {
+            return rng.gen_range(0.01..1.0);
+        }
+
+        match self.population {
+            PopulationType::ThinDisk => rng.gen_range(1.0..10.0),
+            PopulationType::ThickDisk => rng.gen_range(8.0..12.0),
+            PopulationType::Bulge => rng.gen_range(8.0..13.0),
+            PopulationType::Halo => rng.gen_range(10.0..13.5),
+        }
+    }
+}
+
+impl Star {
+    /// Generate a star born in the given environment: in old populations massive
+    /// stars have evolved into giants and remnants, and [Fe/H] sets the heavy
+    /// element content
+    pub fn generate_in_environment(seed: u64, environment: &StellarEnvironment) -> Self {
+        let mut rng = SeedPath::from_seed(seed).stream("star").rng();
+
+        let age = environment.sample_age(&mut rng);
+        if let Some(imf) = environment.imf {
+            let initial_mass = imf.sample(&mut rng);
+            return Self::from_initial_mass(seed, &mut rng, initial_mass, age, environment.metallicity);
+        }
+        let (stellar_type, (min_mass, max_mass)) =
+            StellarType::generate_for_age(&mut rng, age, environment.metallicity);
+        let initial_mass = min_mass + rng.gen::<f64>() * (max_mass - min_mass);
+
+        Self::build(seed, &mut rng, stellar_type, initial_mass, age, environment.metallicity)
+    }
+
+    /// Build a star of the given type, evolved from `initial_mass` solar masses
+    /// to `age` Gyr
+    pub(crate) fn build<R: Rng + ?Sized>(
+        seed: u64,
+        rng: &mut R,
+        stellar_type: StellarType,
+        initial_mass: f64,
+        age: f64,
+        metallicity: f64,
+    ) -> Self {
+        // Mass loss, size and brightness all follow from the star's history;
+        // the temperature is whatever radiates that luminosity from that surface
+        let evolved = evolve(initial_mass, metallicity, age);
+        let mass_solar = evolved.mass;
+        let radius = evolved.radius * 6.957e8;
+        let luminosity = evolved.luminosity;
+        let temp = effective_temperature(luminosity, evolved.radius);
         
         let physical = PhysicalProperties {
             mass: mass_solar * 1.989e30,
@@ -215,11 +337,17 @@ impl Generate for Star {
                 metallicity: 0.0,
                 other: 1.0,
             },
-            _ => Composition {
-                hydrogen: 0.7347,
-                helium: 0.2483,
-                metallicity: 0.0169,
-                other: 0.0001,
+            _ => {
+                // Scale the solar heavy element fraction by [Fe/H]; helium
+                // enrichment tracks metals with dY/dZ ≈ 2
+                let z = 0.0169 * 10f64.powf(metallicity);
+                let helium = 0.2483 + 2.0 * (z - 0.0169);
+                Composition {
+                    hydrogen: 1.0 - helium - z - 0.0001,
+                    helium,
+                    metallicity: z,
+                    other: 0.0001,
+                }
             },
         };
 
@@ -237,14 +365,18 @@ impl Generate for Star {
         };
 
         let mut star = Star {
-            name: format!("Star-{}", seed % 1000),
+            id: SeedPath::from_seed(seed).id(),
+            name: format!("Star-{:016X}", SeedPath::from_seed(seed).id()),
             stellar_type,
             physical,
             composition,
+            metallicity,
+            initial_mass,
             luminosity,
-            age: rng.gen_range(0.1..13.8),
+            age,
             magnetic_field,
             rotation_period,
+            spectral_class: SpectralClass::from_temperature_and_luminosity(temp, luminosity),
         };
 
         star.physical.density = star.physical.calculate_density();
@@ -257,7 +389,9 @@ impl Generate for Star {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolarSystem {
-    pub star: Star,
+    pub seed: u64,
+    pub stars: Vec<Star>,            // primary first, then companions
+    pub companion_orbits: Vec<Orbit>, // stars[k + 1] around the barycentre of stars[..=k]
     pub planets: Vec<Planet>,
     pub total_mass: f64,
     pub system_age: f64,
@@ -270,27 +404,73 @@ impl Generate for SolarSystem {
     }
 
     fn generate_with_seed(seed: u64) -> Self {
-        let mut rng = StdRng::seed_from_u64(seed);
-        
         let star = Star::generate_with_seed(seed);
-        let star_mass = star.physical.mass / 1.989e30; // Convert to solar masses
-        let system_age = star.age;
+        Self::generate_around_star(seed, star)
+    }
+}
+
+impl SolarSystem {
+    /// Generate a system whose star and planets reflect the region's population
+    /// and metallicity
+    pub fn generate_in_region(region: &GalacticRegion, seed: u64) -> Self {
+        let environment = StellarEnvironment::from_region(region);
+        Self::generate_in_environment(seed, &environment)
+    }
+
+    /// Generate a system around a star born in the given environment
+    pub fn generate_in_environment(seed: u64, environment: &StellarEnvironment) -> Self {
+        let star = Star::generate_in_environment(seed, environment);
+        Self::generate_around_star(seed, star)
+    }
+
+    /// Generate a field system whose star's mass is drawn from `imf`
+    pub fn generate_with_imf(seed: u64, imf: InitialMassFunction) -> Self {
+        let star = Star::generate_with_imf(seed, imf);
+        Self::generate_around_star(seed, star)
+    }
+
+    fn generate_around_star(seed: u64, star: Star) -> Self {
+        let path = SeedPath::from_seed(seed);
+        let (stars, companion_orbits) = generate_companions(&path, star);
+        let mut rng = path.stream("layout").rng();
         
-        let habitable_zone = habitable_zone_range(star_mass, star.luminosity);
+        let system_age = stars[0].age;
+        let zones = planet_zones(&stars, &companion_orbits, stars[0].stellar_type.base_orbit_distance());
+
+        // Habitable zone around a zone's host, lit by every star; the system's
+        // is that of the innermost zone
+        let zone_habitable_zone = |zone: &PlanetZone| {
+            let lit_distance = effective_luminosity(&stars, &companion_orbits, zone.host, 1.0).sqrt();
+            habitable_zone_range(
+                zone.mass,
+                effective_luminosity(&stars, &companion_orbits, zone.host, lit_distance),
+            )
+        };
+        let habitable_zone = zone_habitable_zone(&zones[0]);
         
         let mut planets = Vec::new();
+        let mut index = 0;
         
-        if star.stellar_type.can_have_planets() {
+        for zone in &zones {
+            // Circumbinary disks take their character from the primary
+            let star = match zone.host {
+                PlanetHost::Star(i) => &stars[i],
+                PlanetHost::InnerBinary => &stars[0],
+            };
+            if !star.stellar_type.can_have_planets() {
+                continue;
+            }
+
             let (min_planets, max_planets) = star.stellar_type.planet_count_range();
+            // Metal-poor disks run out of solids before forming a full planet system
+            let max_planets = (max_planets as f64 * planet_occurrence_factor(star.metallicity))
+                .round() as usize;
             let num_planets = rng.gen_range(min_planets..=max_planets);
             
             if num_planets > 0 {
-                // Modified Titius-Bode law with randomization
-                let base_distance = match star.stellar_type {
-                    StellarType::BrownDwarf | StellarType::RedDwarf => 0.05,
-                    StellarType::WhiteDwarfRemnant => 0.1,
-                    _ => 0.3, // Increased from 0.2 to spread out planets
-                };
+                // Modified Titius-Bode law with randomization, starting clear of
+                // a circumbinary zone's unstable inner region
+                let base_distance = star.stellar_type.base_orbit_distance().max(zone.inner * 1.3);
 
                 // Calculate spacing factor based on star mass and luminosity
                 let spacing_factor = match star.stellar_type {
@@ -306,77 +486,97 @@ impl Generate for SolarSystem {
                     let distance_factor = rng.gen_range(0.8..1.2); // 20% randomization
                     let distance = bode_distance * distance_factor;
                     
-                    let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-                    
-                    // Generate planet appropriate for this distance
-                    let mut planet = Planet::generate_at_distance(seed + i as u64, distance);
-                    
-                    // Set its position
-                    planet.position = Position {
-                        x: distance * angle.cos() * 1.496e11,
-                        y: distance * angle.sin() * 1.496e11,
-                        z: rng.gen_range(-0.1..0.1) * 1.496e11, // Small inclination
-                    };
-                    
-                    // Calculate surface temperature based on star's properties
-                    let greenhouse_effect = planet.atmosphere.as_ref()
-                        .map(|atm| atm.greenhouse_effect)
-                        .unwrap_or(1.0);
-                    
-                    planet.physical.surface_temperature = calculate_surface_temperature(
-                        distance,
-                        star.luminosity,
-                        greenhouse_effect
+                    // Generate planet appropriate for this distance; its orbit
+                    // places it around the host
+                    let mut planet = Planet::generate_around_star(
+                        path.planet(index).seed(), distance, zone.initial_mass, star.metallicity
                     );
+                    index += 1;
+
+                    // Giant envelopes swallow planets within tidal reach, and the
+                    // survivors drift outward as the host sheds mass
+                    if planet.orbit.periapsis() < 2.0 * zone.engulfing_radius {
+                        continue;
+                    }
+                    planet.adjust_orbit_for_mass_loss(zone.initial_mass, zone.mass);
+                    let distance = planet.orbit.semi_major_axis;
+
+                    // Companions clear out orbits that would cross their stability limits
+                    if planet.orbit.apoapsis() > zone.outer {
+                        break;
+                    }
+                    if planet.orbit.periapsis() < zone.inner {
+                        continue;
+                    }
+                    planet.host = zone.host;
+                    let luminosity = effective_luminosity(&stars, &companion_orbits, zone.host, distance);
+                    let ultraviolet = effective_ultraviolet(&stars, &companion_orbits, zone.host, distance);
                     
-                    // Pass habitable zone information for better habitability assessment
-                    planet.assess_habitability(distance, star_mass);
+                    // Atmosphere and surface temperature follow from the starlight received
+                    planet.set_climate(&mut rng, luminosity, ultraviolet, distance);
+
+                    // Tides act on the body left once the star may have stripped it
+                    planet.despin(zone.mass, system_age);
+                    
+                    planet.assess_habitability(&HostConditions {
+                        habitable_zone: zone_habitable_zone(zone),
+                        stellar_temperature: star.physical.surface_temperature,
+                        age: system_age,
+                        ultraviolet_flux: ultraviolet / distance.powi(2),
+                    });
+
+                    planet.generate_moons(zone.mass, luminosity, system_age);
+                    planet.generate_rings(luminosity);
                     
                     planets.push(planet);
                 }
-
-                // Sort planets by distance from star
-                planets.sort_by(|a, b| {
-                    let dist_a = (a.position.x.powi(2) + a.position.y.powi(2)).sqrt();
-                    let dist_b = (b.position.x.powi(2) + b.position.y.powi(2)).sqrt();
-                    dist_a.partial_cmp(&dist_b).unwrap()
-                });
             }
         }
+
+        // Sort planets by host, then by distance from it
+        planets.sort_by(|a, b| {
+            a.host.cmp(&b.host)
+                .then(a.orbit.semi_major_axis.partial_cmp(&b.orbit.semi_major_axis).unwrap())
+        });
         
-        let total_mass = star.physical.mass + 
+        let total_mass = stars.iter().map(|s| s.physical.mass).sum::<f64>() +
             planets.iter().map(|p| p.physical.mass).sum::<f64>();
 
         SolarSystem {
-            star,
+            seed,
+            stars,
+            companion_orbits,
             planets,
             total_mass,
             system_age,
             habitable_zone,
         }
     }
-}
 
-impl SolarSystem {
+    /// The primary, most massive star of the system
+    pub fn star(&self) -> &Star {
+        &self.stars[0]
+    }
+
     pub fn habitable_planets(&self) -> Vec<&Planet> {
         self.planets.iter().filter(|p| p.habitable).collect()
     }
 
     pub fn center_of_mass(&self) -> Position {
+        let state = self.state_at(0.0, &[]);
         let mut total_weighted_x = 0.0;
         let mut total_weighted_y = 0.0;
         let mut total_weighted_z = 0.0;
         let mut total_mass = 0.0;  // Start at 0 and add all masses
         
-        // Add star's contribution (position 0,0,0)
-        total_mass += self.star.physical.mass;
-        
-        // Add planets' contributions
-        for planet in &self.planets {
-            total_weighted_x += planet.physical.mass * planet.position.x;
-            total_weighted_y += planet.physical.mass * planet.position.y;
-            total_weighted_z += planet.physical.mass * planet.position.z;
-            total_mass += planet.physical.mass;
+        // Stars and planets at the epoch
+        let bodies = self.stars.iter().map(|s| s.physical.mass).zip(&state.stars)
+            .chain(self.planets.iter().map(|p| p.physical.mass).zip(&state.planets));
+        for (mass, body) in bodies {
+            total_weighted_x += mass * body.position.x;
+            total_weighted_y += mass * body.position.y;
+            total_weighted_z += mass * body.position.z;
+            total_mass += mass;
         }
         
         Position {
@@ -390,20 +590,109 @@ impl SolarSystem {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::distributions::{main_sequence_lifetime, main_sequence_luminosity, main_sequence_radius};
 
     #[test]
     fn test_system_generation() {
         let system = SolarSystem::generate();
-        assert!(system.star.physical.mass > 0.0);
+        assert!(system.star().physical.mass > 0.0);
         
-        if system.star.stellar_type.can_have_planets() {
-            let (min, max) = system.star.stellar_type.planet_count_range();
-            assert!(system.planets.len() >= min && system.planets.len() <= max);
+        // Planets around the primary, or around the inner pair; companions'
+        // stability limits can only remove planets
+        let primary_planets = system.planets.iter()
+            .filter(|p| p.host == PlanetHost::Star(0) || p.host == PlanetHost::InnerBinary)
+            .count();
+        if system.star().stellar_type.can_have_planets() {
+            let (min, max) = system.star().stellar_type.planet_count_range();
+            assert!(primary_planets <= max);
+            assert!(system.stars.len() > 1 || primary_planets >= min);
         } else {
-            assert!(system.planets.is_empty());
+            assert_eq!(primary_planets, 0);
         }
         
-        assert!(system.total_mass >= system.star.physical.mass);
+        let star_mass: f64 = system.stars.iter().map(|s| s.physical.mass).sum();
+        assert!(system.total_mass >= star_mass);
+    }
+
+    #[test]
+    fn test_stars_obey_stefan_boltzmann() {
+        let halo = GalacticRegion::generate_at_position(8000.0, 0.0, 5000.0);
+        let young = GalacticRegion::generate_at_position(8000.0, 0.0, 0.0);
+        let systems = (0..2000).map(SolarSystem::generate_with_seed)
+            .chain((0..500).map(|seed| SolarSystem::generate_in_region(&halo, seed)))
+            .chain((0..500).map(|seed| SolarSystem::generate_in_region(&young, seed)));
+
+        for star in systems.flat_map(|s| s.stars) {
+            let r = star.physical.radius;
+            let t = star.physical.surface_temperature;
+            let luminosity = 4.0 * std::f64::consts::PI * r * r * 5.670374e-8 * t.powi(4) / 3.828e26;
+            if star.luminosity == 0.0 {
+                assert_eq!(luminosity, 0.0);
+            } else {
+                assert!((luminosity / star.luminosity - 1.0).abs() < 1e-6, "{:?}", star.stellar_type);
+            }
+        }
+    }
+
+    #[test]
+    fn test_main_sequence_follows_mass() {
+        // Heavier dwarfs are larger, brighter and hotter at birth
+        let masses: Vec<f64> = (1..300).map(|i| 0.08 + i as f64 * 0.05).collect();
+        for pair in masses.windows(2) {
+            let temperature = |m: f64| effective_temperature(main_sequence_luminosity(m), main_sequence_radius(m));
+            assert!(main_sequence_radius(pair[1]) > main_sequence_radius(pair[0]));
+            assert!(main_sequence_luminosity(pair[1]) > main_sequence_luminosity(pair[0]));
+            assert!(temperature(pair[1]) > temperature(pair[0]));
+        }
+
+        // and brighten only modestly as they age
+        for star in (0..2000).flat_map(|seed| SolarSystem::generate_with_seed(seed).stars) {
+            if star.stellar_type.is_main_sequence() && star.stellar_type != StellarType::BrownDwarf {
+                let ratio = star.luminosity / main_sequence_luminosity(star.initial_mass);
+                assert!((0.72..=1.32).contains(&ratio));
+            }
+        }
+
+        // A solar-mass star reproduces the Sun
+        assert!((main_sequence_luminosity(1.0) - 1.0).abs() < 1e-9);
+        assert!((effective_temperature(1.0, main_sequence_radius(1.0)) - 5772.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_imf_stars_match_their_age() {
+        for imf in [InitialMassFunction::Kroupa, InitialMassFunction::Chabrier] {
+            let systems: Vec<SolarSystem> = (0..2000).map(|seed| SolarSystem::generate_with_imf(seed, imf)).collect();
+            let fraction = |t: StellarType| {
+                systems.iter().filter(|s| s.star().stellar_type == t).count() as f64 / systems.len() as f64
+            };
+
+            for star in systems.iter().flat_map(|s| &s.stars) {
+                let mass = star.physical.mass / 1.989e30;
+                if star.stellar_type.is_main_sequence() && star.stellar_type != StellarType::BrownDwarf {
+                    assert!(star.age < main_sequence_lifetime(mass));
+                }
+                if star.stellar_type == StellarType::WhiteDwarfRemnant {
+                    assert!(mass < 1.4);
+                }
+            }
+
+            // Low-mass stars dominate and massive stars are rare
+            assert!(fraction(StellarType::RedDwarf) > 0.3, "{:?}", imf);
+            assert!(fraction(StellarType::BrownDwarf) > 0.1, "{:?}", imf);
+            assert!(fraction(StellarType::BlueSupergiant) < 0.01, "{:?}", imf);
+            assert!(fraction(StellarType::WhiteDwarfRemnant) > 0.0, "{:?}", imf);
+        }
+
+        // Environments can swap the frequency table for an IMF
+        let region = GalacticRegion::generate_at_position(8000.0, 0.0, 5000.0);
+        let environment = StellarEnvironment {
+            imf: Some(InitialMassFunction::Kroupa),
+            ..StellarEnvironment::from_region(&region)
+        };
+        let system = SolarSystem::generate_in_environment(3, &environment);
+        assert_eq!(system.star().metallicity, region.metallicity);
+        let mass = system.star().physical.mass / 1.989e30;
+        assert!(!system.star().stellar_type.is_main_sequence() || mass < 1.0);
     }
 
     #[test]
@@ -414,18 +703,63 @@ mod tests {
         assert!(inner > 0.0);
     }
 
+    #[test]
+    fn test_region_conditions_system() {
+        let thin_disk = GalacticRegion::generate_at_position(8000.0, 0.0, 0.0);
+        let bulge = GalacticRegion::generate_at_position(500.0, 0.0, 0.0);
+        let halo = GalacticRegion::generate_at_position(8000.0, 0.0, 5000.0);
+
+        let sample = |region: &GalacticRegion| -> Vec<SolarSystem> {
+            (0..500).map(|seed| SolarSystem::generate_in_region(region, seed)).collect()
+        };
+        let mean = |systems: &[SolarSystem], f: fn(&SolarSystem) -> f64| {
+            systems.iter().map(f).sum::<f64>() / systems.len() as f64
+        };
+        let thin_disk_systems = sample(&thin_disk);
+        let bulge_systems = sample(&bulge);
+        let halo_systems = sample(&halo);
+
+        // Stars inherit the region's [Fe/H]
+        assert!(halo_systems.iter().all(|s| s.star().metallicity == halo.metallicity));
+        assert!(bulge_systems[0].star().composition.metallicity
+            > thin_disk_systems[0].star().composition.metallicity);
+
+        // Halo stars are old, low mass and planet-poor
+        assert!(halo_systems.iter().all(|s| s.system_age >= 10.0));
+        assert!(halo_systems.iter()
+            .filter(|s| s.star().stellar_type.is_main_sequence())
+            .all(|s| s.star().physical.mass / 1.989e30 < 1.0));
+        assert!(mean(&halo_systems, |s| s.planets.len() as f64)
+            < mean(&thin_disk_systems, |s| s.planets.len() as f64));
+
+        // Young thin disk populations still host massive stars
+        assert!(thin_disk_systems.iter().any(|s| s.star().physical.mass / 1.989e30 > 2.0
+            && s.star().stellar_type.is_main_sequence()));
+
+        // Giant planets are more common around metal-rich stars
+        let giant_fraction = |systems: &[SolarSystem]| {
+            let planets: Vec<&Planet> = systems.iter().flat_map(|s| &s.planets).collect();
+            planets.iter().filter(|p| p.physical.mass > 50.0 * 5.972e24).count() as f64
+                / planets.len().max(1) as f64
+        };
+        assert!(giant_fraction(&bulge_systems) > giant_fraction(&halo_systems));
+    }
+
     #[test]
     fn test_center_of_mass() {
         let system = SolarSystem::generate();
         let com = system.center_of_mass();
         
-        let system_size = system.planets.iter()
-            .map(|p| (p.position.x.powi(2) + p.position.y.powi(2)).sqrt())
+        let state = system.state_at(0.0, &[]);
+        let system_size = state.stars.iter().chain(&state.planets)
+            .map(|b| (b.position.x.powi(2) + b.position.y.powi(2)).sqrt())
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap_or(0.0);
             
-        assert!(com.x.abs() < system_size / 10.0);
-        assert!(com.y.abs() < system_size / 10.0);
-        assert!(com.z.abs() < system_size / 10.0);
+        // Lone stars without planets (brown dwarfs, remnants) have a size of zero
+        // and their centre of mass exactly on the star, so the bound is inclusive
+        assert!(com.x.abs() <= system_size / 10.0);
+        assert!(com.y.abs() <= system_size / 10.0);
+        assert!(com.z.abs() <= system_size / 10.0);
     }
 }
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use crate::{Galaxy, Position, SectorCoord, StarStub, SECTOR_SIZE};
+
+/// A catalogued star and its distance from a query point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyStar {
+    pub star: StarStub,
+    pub distance: f64, // parsecs
+}
+
+impl SectorCoord {
+    /// Shortest distance (parsecs) from a point to any point of the sector
+    pub fn distance_to(&self, point: &Position) -> f64 {
+        let corner = self.min_corner();
+        let gap = |p: f64, low: f64| (low - p).max(0.0).max(p - (low + SECTOR_SIZE));
+        let (dx, dy, dz) = (gap(point.x, corner.x), gap(point.y, corner.y), gap(point.z, corner.z));
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+}
+
+fn distance(star: &StarStub, point: &Position) -> f64 {
+    let p = &star.position;
+    ((p.x - point.x).powi(2) + (p.y - point.y).powi(2) + (p.z - point.z).powi(2)).sqrt()
+}
+
+fn sort_by_distance(stars: &mut [NearbyStar]) {
+    stars.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap().then(a.star.id.cmp(&b.star.id)));
+}
+
+impl Galaxy {
+    /// Lowest and highest sectors that can hold stars: those within the
+    /// halo's outer radius of the galaxy's centre along every axis
+    fn sector_bounds(&self) -> (SectorCoord, SectorCoord) {
+        let outer = self.halo.outer_radius;
+        (
+            SectorCoord::containing(-outer, -outer, -outer),
+            SectorCoord::containing(outer, outer, outer),
+        )
+    }
+
+    /// Distance (parsecs) from `center` to the far side of the galaxy, beyond
+    /// which no star lies
+    fn reach(&self, center: &Position) -> f64 {
+        (center.x * center.x + center.y * center.y + center.z * center.z).sqrt() + self.halo.outer_radius
+    }
+
+    /// A search distance from `center` clamped to the galaxy's reach, or None
+    /// when it is NaN or negative
+    fn search_distance(&self, center: &Position, distance: f64) -> Option<f64> {
+        (distance >= 0.0).then(|| distance.min(self.reach(center)))
+    }
+
+    /// All stars within `radius` parsecs of `center`, nearest first
+    ///
+    /// The galaxy ends at its halo's outer radius, so an infinite radius finds
+    /// every star; a NaN or negative one finds none.
+    pub fn stars_within(&self, center: Position, radius: f64) -> Vec<NearbyStar> {
+        let Some(radius) = self.search_distance(&center, radius) else {
+            return Vec::new();
+        };
+        let (bottom, top) = self.sector_bounds();
+        let low = SectorCoord::containing(center.x - radius, center.y - radius, center.z - radius);
+        let high = SectorCoord::containing(center.x + radius, center.y + radius, center.z + radius);
+        let low = SectorCoord { x: low.x.max(bottom.x), y: low.y.max(bottom.y), z: low.z.max(bottom.z) };
+        let high = SectorCoord { x: high.x.min(top.x), y: high.y.min(top.y), z: high.z.min(top.z) };
+
+        let mut found = Vec::new();
+        for x in low.x..=high.x {
+            for y in low.y..=high.y {
+                for z in low.z..=high.z {
+                    let sector = SectorCoord { x, y, z };
+                    if sector.distance_to(&center) > radius {
+                        continue;
+                    }
+                    for star in self.sector_stars(sector) {
+                        let d = distance(&star, &center);
+                        if d <= radius {
+                            found.push(NearbyStar { star, distance: d });
+                        }
+                    }
+                }
+            }
+        }
+
+        sort_by_distance(&mut found);
+        found
+    }
+
+    /// The `k` stars nearest to `center`, nearest first, no further than
+    /// `max_distance` parsecs if given
+    ///
+    /// Sectors are scanned in shells of increasing distance, stopping once no
+    /// unscanned sector can hold a closer star than the k-th found so far, or
+    /// once the shells have passed the far side of the galaxy. Only the part
+    /// of each shell inside the galaxy's bounds is scanned, so a query from
+    /// empty space ends quickly. A NaN or negative `max_distance` finds no stars.
+    pub fn nearest_stars(&self, center: Position, k: usize, max_distance: Option<f64>) -> Vec<NearbyStar> {
+        let max_distance = match max_distance {
+            Some(distance) => self.search_distance(&center, distance),
+            None => Some(self.reach(&center)),
+        };
+        let Some(max_distance) = max_distance.filter(|_| k > 0) else {
+            return Vec::new();
+        };
+
+        let origin = SectorCoord::containing(center.x, center.y, center.z);
+        let corner = origin.min_corner();
+        // Distance from the query point to the nearest face of its own sector
+        let face = [center.x - corner.x, center.y - corner.y, center.z - corner.z]
+            .iter()
+            .map(|&offset| offset.min(SECTOR_SIZE - offset))
+            .fold(f64::MAX, f64::min);
+
+        // Offsets of the galaxy's bounding sectors from the query's sector;
+        // shells before the first to reach them are empty
+        let (bottom, top) = self.sector_bounds();
+        let low = [bottom.x - origin.x, bottom.y - origin.y, bottom.z - origin.z];
+        let high = [top.x - origin.x, top.y - origin.y, top.z - origin.z];
+        let first_shell = (0..3).map(|i| low[i].max(-high[i]).max(0)).max().unwrap();
+
+        let mut found: Vec<NearbyStar> = Vec::new();
+        let mut shell = first_shell;
+        loop {
+            // Closest any sector in this shell can be to the query point
+            let shell_bound = if shell == 0 { 0.0 } else { (shell - 1) as f64 * SECTOR_SIZE + face };
+            let kth_distance = if found.len() >= k { found[k - 1].distance } else { max_distance };
+            if shell_bound > kth_distance {
+                break;
+            }
+
+            let (z_low, z_high) = (low[2].max(-shell), high[2].min(shell));
+            for x in low[0].max(-shell)..=high[0].min(shell) {
+                for y in low[1].max(-shell)..=high[1].min(shell) {
+                    // Every z on the shell's x and y faces, only its two z faces elsewhere
+                    let (first_z, step) = if x.abs() == shell || y.abs() == shell {
+                        (z_low, 1)
+                    } else {
+                        (-shell, 2 * shell as usize)
+                    };
+                    for z in (first_z..=z_high).step_by(step).filter(|&z| z >= z_low) {
+                        let sector = SectorCoord { x: origin.x + x, y: origin.y + y, z: origin.z + z };
+                        if sector.distance_to(&center) > kth_distance {
+                            continue;
+                        }
+                        for star in self.sector_stars(sector) {
+                            let d = distance(&star, &center);
+                            if d <= max_distance {
+                                found.push(NearbyStar { star, distance: d });
+                            }
+                        }
+                    }
+                }
+            }
+
+            sort_by_distance(&mut found);
+            found.truncate(k);
+            shell += 1;
+        }
+
+        found
+    }
+
+    /// The star closest to `center`, within `max_distance` parsecs if given
+    pub fn nearest_star(&self, center: Position, max_distance: Option<f64>) -> Option<NearbyStar> {
+        self.nearest_stars(center, 1, max_distance).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(galaxy: &Galaxy, center: Position, radius: f64) -> Vec<NearbyStar> {
+        let mut all: Vec<NearbyStar> = galaxy
+            .stars_in_volume(
+                Position { x: center.x - radius, y: center.y - radius, z: center.z - radius },
+                Position { x: center.x + radius, y: center.y + radius, z: center.z + radius },
+            )
+            .into_iter()
+            .map(|star| NearbyStar { distance: distance(&star, &center), star })
+            .filter(|s| s.distance <= radius)
+            .collect();
+        sort_by_distance(&mut all);
+        all
+    }
+
+    #[test]
+    fn test_stars_within_matches_brute_force() {
+        let galaxy = Galaxy::new();
+        let center = Position { x: 8003.0, y: 17.5, z: -4.2 };
+        let found = galaxy.stars_within(center, 25.0);
+        let expected = brute_force(&galaxy, center, 25.0);
+
+        assert!(!found.is_empty());
+        assert_eq!(found.len(), expected.len());
+        for (a, b) in found.iter().zip(&expected) {
+            assert_eq!(a.star.id, b.star.id);
+        }
+        assert!(found.windows(2).all(|w| w[0].distance <= w[1].distance));
+    }
+
+    #[test]
+    fn test_nearest_stars_exact() {
+        let galaxy = Galaxy::new();
+        let center = Position { x: 7999.1, y: -3.3, z: 9.9 };
+        let nearest = galaxy.nearest_stars(center, 20, Some(1000.0));
+        assert_eq!(nearest.len(), 20);
+
+        // The k nearest are exactly the first k of an exhaustive search
+        let radius = nearest.last().unwrap().distance;
+        let expected = brute_force(&galaxy, center, radius);
+        for (a, b) in nearest.iter().zip(&expected) {
+            assert_eq!(a.star.id, b.star.id);
+        }
+
+        let closest = galaxy.nearest_star(center, Some(1000.0)).unwrap();
+        assert_eq!(closest.star.id, nearest[0].star.id);
+    }
+
+    #[test]
+    fn test_nearest_respects_max_distance() {
+        let galaxy = Galaxy::new();
+        let center = Position { x: 8000.0, y: 0.0, z: 0.0 };
+        let nearest = galaxy.nearest_stars(center, 1000, Some(12.0));
+        assert!(nearest.iter().all(|s| s.distance <= 12.0));
+        assert_eq!(nearest.len(), galaxy.stars_within(center, 12.0).len());
+
+        // Intergalactic space is empty, and the search still terminates
+        let void = Position { x: 1e6, y: 1e6, z: 1e6 };
+        assert!(galaxy.nearest_stars(void, 5, Some(30.0)).is_empty());
+    }
+
+    #[test]
+    fn test_far_out_search_ends() {
+        // The whole galaxy lies beyond reach of a point 1 Mpc out
+        let galaxy = Galaxy::new();
+        let far = Position { x: 1e6, y: 0.0, z: 0.0 };
+        assert!(galaxy.nearest_stars(far, 5, Some(1e5)).is_empty());
+        assert!(galaxy.stars_within(far, 1e5).is_empty());
+    }
+
+    #[test]
+    fn test_nearest_stars_match_catalogue() {
+        // Above the disk in the sparse halo, asking for more stars than lie
+        // within reach returns every catalogued star in range
+        let galaxy = Galaxy::new();
+        let center = Position { x: 8000.0, y: 0.0, z: 2000.0 };
+        let all = galaxy.nearest_stars(center, 100_000, Some(40.0));
+        let expected = brute_force(&galaxy, center, 40.0);
+        assert!(!expected.is_empty());
+        assert_eq!(all.len(), expected.len());
+        for (a, b) in all.iter().zip(&expected) {
+            assert_eq!(a.star.id, b.star.id);
+        }
+    }
+
+    #[test]
+    fn test_unbounded_search_covers_the_galaxy() {
+        // A galaxy small enough to list every star, without the halo's dense cusp
+        let mut galaxy = Galaxy::with_morphology(crate::GalaxyMorphology::Irregular);
+        galaxy.radius = 40.0;
+        galaxy.halo.outer_radius = 50.0;
+        galaxy.halo.reference_density = 0.0;
+        let center = Position { x: 0.0, y: 0.0, z: 0.0 };
+        let all = galaxy.stars_within(center, galaxy.halo.outer_radius);
+        assert!(!all.is_empty());
+
+        let ids = |stars: &[NearbyStar]| stars.iter().map(|s| s.star.id).collect::<Vec<_>>();
+        assert_eq!(ids(&galaxy.stars_within(center, f64::INFINITY)), ids(&all));
+        assert_eq!(ids(&galaxy.nearest_stars(center, usize::MAX, None)), ids(&all));
+        assert_eq!(ids(&galaxy.nearest_stars(center, usize::MAX, Some(f64::INFINITY))), ids(&all));
+
+        // Only the k nearest, with no distance cap
+        assert_eq!(ids(&galaxy.nearest_stars(center, 5, None)), ids(&all[..5]));
+
+        for distance in [f64::NAN, -1.0] {
+            assert!(galaxy.nearest_stars(center, 5, Some(distance)).is_empty());
+            assert!(galaxy.stars_within(center, distance).is_empty());
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Morgan–Keenan spectral classification: "G2V", "M4.5V", "K1III", "DA3".
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use crate::distributions::{
+    blackbody_luminosity, effective_temperature, main_sequence_luminosity, main_sequence_radius,
+};
+
+/// Harvard temperature sequence, hottest first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum HarvardClass {
+    O, B, A, F, G, K, M,
+    L, T, Y,  // brown dwarfs
+}
+
+/// Yerkes luminosity class, from hypergiants down to dwarfs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum LuminosityClass {
+    Hypergiant,      // Ia+
+    LuminousSupergiant, // Ia
+    Supergiant,      // Ib
+    BrightGiant,     // II
+    Giant,           // III
+    Subgiant,        // IV
+    Dwarf,           // V, the main sequence
+}
+
+/// White dwarf spectral type, by the strongest features of the atmosphere
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum WhiteDwarfClass {
+    DA,  // hydrogen lines
+    DB,  // neutral helium
+    DO,  // ionised helium, the hottest
+    DC,  // featureless, too cool to show lines
+    DQ,  // carbon
+    DZ,  // metals
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SpectralClass {
+    Normal {
+        class: HarvardClass,
+        subclass: f64,  // 0 (hottest) to 9.5 in half steps
+        luminosity_class: Option<LuminosityClass>,
+    },
+    WhiteDwarf {
+        class: WhiteDwarfClass,
+        temperature_index: f64,  // 50400 K / T_eff, in half steps
+    },
+}
+
+/// Effective temperature (K) along the dwarf sequence, keyed by a numeric
+/// code of 10 per class from O0 = 0 to Y0 = 90 (after Pecaut & Mamajek 2013)
+const SPECTRAL_SEQUENCE: [(f64, f64); 37] = [
+    (2.0, 50000.0), (3.0, 44900.0), (5.0, 41400.0), (7.0, 36900.0), (9.0, 33000.0),
+    (10.0, 31400.0), (12.0, 20600.0), (15.0, 15700.0), (18.0, 12500.0),
+    (20.0, 9700.0), (25.0, 8180.0),
+    (30.0, 7220.0), (35.0, 6510.0),
+    (40.0, 5920.0), (42.0, 5770.0), (45.0, 5660.0),
+    (50.0, 5280.0), (51.0, 5170.0), (52.0, 5040.0), (53.0, 4830.0), (54.0, 4600.0), (55.0, 4450.0), (57.0, 4050.0),
+    (60.0, 3850.0), (62.0, 3560.0), (64.0, 3210.0), (65.0, 3060.0), (66.0, 2810.0), (68.0, 2570.0),
+    (70.0, 2250.0), (75.0, 1600.0),
+    (80.0, 1260.0), (85.0, 1150.0), (88.0, 700.0),
+    (90.0, 450.0), (92.0, 350.0), (94.0, 250.0),
+];
+
+const HARVARD_CLASSES: [HarvardClass; 10] = [
+    HarvardClass::O, HarvardClass::B, HarvardClass::A, HarvardClass::F, HarvardClass::G,
+    HarvardClass::K, HarvardClass::M, HarvardClass::L, HarvardClass::T, HarvardClass::Y,
+];
+
+impl SpectralClass {
+    /// Classify a star from its effective temperature (K) and luminosity (solar).
+    /// Neutron stars and black holes have no spectral class.
+    pub fn from_temperature_and_luminosity(temperature: f64, luminosity: f64) -> Option<Self> {
+        if !(temperature > 0.0 && luminosity > 0.0) {
+            return None;
+        }
+        let radius = (luminosity / blackbody_luminosity(1.0, temperature)).sqrt();
+
+        match radius {
+            r if r < 1e-3 => None,
+            // Degenerate stars about the size of the Earth
+            r if r < 0.05 && temperature > 3000.0 => {
+                let class = match temperature {
+                    t if t > 45000.0 => WhiteDwarfClass::DO,
+                    t if t > 5000.0 => WhiteDwarfClass::DA,
+                    _ => WhiteDwarfClass::DC,
+                };
+                Some(SpectralClass::WhiteDwarf { class, temperature_index: half_step(50400.0 / temperature) })
+            }
+            _ => {
+                let (class, subclass) = harvard_class(temperature);
+                Some(SpectralClass::Normal {
+                    class,
+                    subclass,
+                    luminosity_class: Some(luminosity_class(temperature, luminosity)),
+                })
+            }
+        }
+    }
+}
+
+fn half_step(x: f64) -> f64 {
+    (x * 2.0).round() / 2.0
+}
+
+/// Harvard class and subclass, interpolating log temperature along the sequence
+fn harvard_class(temperature: f64) -> (HarvardClass, f64) {
+    let (first, last) = (SPECTRAL_SEQUENCE[0], SPECTRAL_SEQUENCE[SPECTRAL_SEQUENCE.len() - 1]);
+    let code = if temperature >= first.1 {
+        first.0
+    } else if temperature <= last.1 {
+        last.0
+    } else {
+        let i = SPECTRAL_SEQUENCE.iter().position(|&(_, t)| t < temperature).unwrap();
+        let ((hot_code, hot), (cool_code, cool)) = (SPECTRAL_SEQUENCE[i - 1], SPECTRAL_SEQUENCE[i]);
+        hot_code + (cool_code - hot_code) * (hot / temperature).ln() / (hot / cool).ln()
+    };
+    let code = half_step(code);
+    (HARVARD_CLASSES[(code / 10.0) as usize], code % 10.0)
+}
+
+/// Luminosity class from how far a star outshines a dwarf of its temperature
+fn luminosity_class(temperature: f64, luminosity: f64) -> LuminosityClass {
+    let excess = luminosity / main_sequence_luminosity_at(temperature);
+    match luminosity {
+        _ if excess < 4.0 => LuminosityClass::Dwarf,
+        _ if excess < 20.0 => LuminosityClass::Subgiant,
+        l if l < 3e3 => LuminosityClass::Giant,
+        l if l < 1e4 => LuminosityClass::BrightGiant,
+        l if l < 1e5 => LuminosityClass::Supergiant,
+        l if l < 3e5 => LuminosityClass::LuminousSupergiant,
+        _ => LuminosityClass::Hypergiant,
+    }
+}
+
+/// Zero-age main sequence luminosity (solar) at the given temperature
+fn main_sequence_luminosity_at(temperature: f64) -> f64 {
+    let temperature_of = |m: f64| effective_temperature(main_sequence_luminosity(m), main_sequence_radius(m));
+    // Temperature rises with mass along the main sequence, so bisect in log mass
+    let (mut low, mut high) = (0.08f64.ln(), 150f64.ln());
+    for _ in 0..60 {
+        let mid = (low + high) / 2.0;
+        if temperature_of(mid.exp()) < temperature { low = mid } else { high = mid }
+    }
+    main_sequence_luminosity(low.exp())
+}
+
+/// "2", "4.5"
+fn format_number(x: f64) -> String {
+    if x.fract() == 0.0 { format!("{:.0}", x) } else { format!("{}", x) }
+}
+
+impl fmt::Display for LuminosityClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LuminosityClass::Hypergiant => "Ia+",
+            LuminosityClass::LuminousSupergiant => "Ia",
+            LuminosityClass::Supergiant => "Ib",
+            LuminosityClass::BrightGiant => "II",
+            LuminosityClass::Giant => "III",
+            LuminosityClass::Subgiant => "IV",
+            LuminosityClass::Dwarf => "V",
+        })
+    }
+}
+
+impl fmt::Display for SpectralClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpectralClass::Normal { class, subclass, luminosity_class } => {
+                write!(f, "{:?}{}", class, format_number(*subclass))?;
+                if let Some(luminosity_class) = luminosity_class {
+                    write!(f, "{}", luminosity_class)?;
+                }
+                Ok(())
+            }
+            SpectralClass::WhiteDwarf { class, temperature_index } => {
+                write!(f, "{:?}{}", class, format_number(*temperature_index))
+            }
+        }
+    }
+}
+
+/// A designation that is not a valid MK spectral class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSpectralClassError(String);
+
+impl fmt::Display for ParseSpectralClassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid spectral class {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSpectralClassError {}
+
+impl FromStr for SpectralClass {
+    type Err = ParseSpectralClassError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSpectralClassError(s.to_string());
+        let s = s.trim();
+
+        // Split the leading letters, the number and the trailing luminosity class
+        let letters_end = s.find(|c: char| !c.is_ascii_alphabetic()).ok_or_else(error)?;
+        let (letters, rest) = s.split_at(letters_end);
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let (number, suffix) = rest.split_at(number_end);
+        let number: f64 = number.parse().map_err(|_| error())?;
+
+        if letters.len() == 2 && letters.starts_with('D') {
+            let class = match letters {
+                "DA" => WhiteDwarfClass::DA,
+                "DB" => WhiteDwarfClass::DB,
+                "DO" => WhiteDwarfClass::DO,
+                "DC" => WhiteDwarfClass::DC,
+                "DQ" => WhiteDwarfClass::DQ,
+                "DZ" => WhiteDwarfClass::DZ,
+                _ => return Err(error()),
+            };
+            if !suffix.is_empty() {
+                return Err(error());
+            }
+            return Ok(SpectralClass::WhiteDwarf { class, temperature_index: number });
+        }
+
+        let class = match letters {
+            "O" => HarvardClass::O,
+            "B" => HarvardClass::B,
+            "A" => HarvardClass::A,
+            "F" => HarvardClass::F,
+            "G" => HarvardClass::G,
+            "K" => HarvardClass::K,
+            "M" => HarvardClass::M,
+            "L" => HarvardClass::L,
+            "T" => HarvardClass::T,
+            "Y" => HarvardClass::Y,
+            _ => return Err(error()),
+        };
+        if !(0.0..10.0).contains(&number) {
+            return Err(error());
+        }
+        let luminosity_class = match suffix {
+            "" => None,
+            "Ia+" => Some(LuminosityClass::Hypergiant),
+            "Ia" => Some(LuminosityClass::LuminousSupergiant),
+            "Ib" | "I" => Some(LuminosityClass::Supergiant),
+            "II" => Some(LuminosityClass::BrightGiant),
+            "III" => Some(LuminosityClass::Giant),
+            "IV" => Some(LuminosityClass::Subgiant),
+            "V" => Some(LuminosityClass::Dwarf),
+            _ => return Err(error()),
+        };
+
+        Ok(SpectralClass::Normal { class, subclass: number, luminosity_class })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generate, SolarSystem, StellarType};
+
+    fn classify(temperature: f64, luminosity: f64) -> String {
+        SpectralClass::from_temperature_and_luminosity(temperature, luminosity).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_reference_stars() {
+        assert_eq!(classify(5772.0, 1.0), "G2V");                  // the Sun
+        assert_eq!(classify(3100.0, 0.0036), "M4.5V");             // Barnard's Star
+        assert_eq!(classify(5150.0, 60.0), "K1III");               // a red clump giant
+        assert_eq!(classify(16800.0, 0.0056), "DA3");              // a typical white dwarf
+        assert!(classify(3600.0, 1.2e5).starts_with("M1"));        // Betelgeuse
+        assert!(classify(3600.0, 1.2e5).ends_with("Ia"));
+        assert!(SpectralClass::from_temperature_and_luminosity(1e6, 0.3).is_none());  // neutron star
+        assert!(SpectralClass::from_temperature_and_luminosity(0.0, 0.0).is_none());  // black hole
+        assert!(SpectralClass::from_temperature_and_luminosity(f64::NAN, 1.0).is_none());
+        assert!(SpectralClass::from_temperature_and_luminosity(5772.0, f64::NAN).is_none());
+    }
+
+    #[test]
+    fn test_parsing() {
+        for designation in ["G2V", "M4.5V", "K1III", "DA3", "B0Ia+", "O9.5Ib", "A0", "T7.5V", "DC12"] {
+            let class: SpectralClass = designation.parse().unwrap();
+            assert_eq!(class.to_string(), designation);
+        }
+        assert_eq!(
+            "K1III".parse::<SpectralClass>().unwrap(),
+            SpectralClass::Normal { class: HarvardClass::K, subclass: 1.0, luminosity_class: Some(LuminosityClass::Giant) },
+        );
+        for invalid in ["", "G", "X2V", "G2VI", "G12V", "DX3", "DA", "DA3V"] {
+            assert!(invalid.parse::<SpectralClass>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_generated_stars_are_classified() {
+        for star in (0..2000).flat_map(|seed| SolarSystem::generate_with_seed(seed).stars) {
+            let designation = star.spectral_class.map(|c| c.to_string());
+            match star.stellar_type {
+                StellarType::YellowDwarf => {
+                    let designation = designation.unwrap();
+                    assert!(designation.ends_with('V'));
+                    assert!(["F", "G", "K"].iter().any(|c| designation.starts_with(c)), "{}", designation);
+                }
+                StellarType::RedDwarf => assert!(designation.unwrap().starts_with('M')),
+                StellarType::WhiteDwarfRemnant => assert!(designation.unwrap().starts_with('D')),
+                // Stars just leaving the main sequence are still hot subgiants
+                StellarType::RedGiant if star.physical.surface_temperature < 5500.0 => {
+                    assert!(!designation.unwrap().ends_with('V'))
+                }
+                StellarType::BlackHole | StellarType::NeutronStar => assert!(designation.is_none()),
+                _ => {}
+            }
+
+            if let Some(class) = star.spectral_class {
+                assert_eq!(class.to_string().parse::<SpectralClass>().unwrap(), class);
+            }
+        }
+    }
+}
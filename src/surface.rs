@@ -0,0 +1,272 @@
+//! Procedural terrain for solid planets.
+//!
+//! Heights are fractal noise evaluated on the unit sphere in three
+//! dimensions, so the terrain has no seam at the date line and no pinching
+//! at the poles, and any point can be sampled without building a grid.
+
+use serde::{Deserialize, Serialize};
+use crate::noise::fbm;
+use crate::seed::SeedPath;
+use crate::{Planet, PlanetType};
+
+/// Relief (m) of an Earth-gravity world, from the deepest trenches to the highest peaks
+const EARTH_RELIEF: f64 = 10000.0;
+
+/// Largest relief as a fraction of the radius; beyond it rock yields under its own weight
+const MAX_RELATIVE_RELIEF: f64 = 0.05;
+
+/// Global equivalent depth (m) of the Earth's oceans
+const EARTH_OCEAN_DEPTH: f64 = 2700.0;
+
+/// Sample points used to place sea level
+const SEA_LEVEL_SAMPLES: usize = 4096;
+
+/// Tectonic activity from 0 (a dead, cooled lid) to ~1 (vigorous plate
+/// tectonics) of a rocky body of `mass` Earth masses and `age` billion years.
+/// Radiogenic heat halves every ~3 billion years; larger planets hold it longer.
+pub fn tectonic_activity(mass: f64, age: f64) -> f64 {
+    (0.8 * mass.max(0.0).sqrt() * 2f64.powf(-(age - 4.6) / 3.0)).clamp(0.0, 1.0)
+}
+
+/// Terrain of a solid planet, sampled by latitude and longitude
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Surface {
+    pub seed: u64,                // the planet's; each noise layer draws its own stream from it
+    pub radius: f64,              // in meters
+    pub relief: f64,              // in meters, typical height of the highest terrain above the datum
+    pub tectonic_activity: f64,   // 0 to 1
+    pub erosion: f64,             // 0 (fresh) to 1 (worn smooth)
+    pub sea_level: Option<f64>,   // in meters above the datum; None on dry worlds
+}
+
+/// Equirectangular grid of heights (m), row-major from the north pole down,
+/// with pixel centres spaced evenly in latitude and longitude
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    pub heights: Vec<f64>,
+}
+
+impl Heightmap {
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        self.heights[y * self.width + x]
+    }
+
+    /// Latitude (degrees) of the centre of row `y`
+    pub fn latitude(&self, y: usize) -> f64 {
+        grid_latitude(y, self.height)
+    }
+
+    /// Longitude (degrees, -180 to 180) of the centre of column `x`
+    pub fn longitude(&self, x: usize) -> f64 {
+        grid_longitude(x, self.width)
+    }
+}
+
+/// Latitude (degrees) of the centre of row `y` of an equirectangular grid
+pub(crate) fn grid_latitude(y: usize, rows: usize) -> f64 {
+    90.0 - (y as f64 + 0.5) * 180.0 / rows as f64
+}
+
+/// Longitude (degrees) of the centre of column `x` of an equirectangular grid
+pub(crate) fn grid_longitude(x: usize, columns: usize) -> f64 {
+    -180.0 + (x as f64 + 0.5) * 360.0 / columns as f64
+}
+
+/// Unit vector towards a latitude and longitude in degrees
+fn unit_vector(latitude: f64, longitude: f64) -> [f64; 3] {
+    let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+impl Surface {
+    /// Terrain for a planet with `surface_gravity` m/s² and `radius` m whose
+    /// surface holds `ocean_depth` m of liquid water, spread evenly
+    pub fn new(seed: u64, radius: f64, surface_gravity: f64, tectonic_activity: f64, erosion: f64, ocean_depth: f64) -> Self {
+        // Mountains stand taller where gravity is weaker (Olympus Mons on Mars)
+        let relief = (EARTH_RELIEF * 9.81 / surface_gravity.max(1e-3)).min(MAX_RELATIVE_RELIEF * radius);
+        let mut surface = Surface {
+            seed,
+            radius,
+            relief,
+            tectonic_activity: tectonic_activity.clamp(0.0, 1.0),
+            erosion: erosion.clamp(0.0, 1.0),
+            sea_level: None,
+        };
+        if ocean_depth > 0.0 {
+            surface.sea_level = Some(surface.sea_level_for(ocean_depth));
+        }
+        surface
+    }
+
+    /// Height (m) above the datum at a latitude and longitude in degrees
+    pub fn height(&self, latitude: f64, longitude: f64) -> f64 {
+        let [x, y, z] = unit_vector(latitude, longitude);
+        let path = SeedPath::from_planet_seed(self.seed);
+
+        // Continents and basins: active planets sort their crust into high
+        // continents and deep ocean floors, dead ones keep a single level
+        let continents = fbm(path.stream("surface").seed(), 1.5 * x, 1.5 * y, 1.5 * z, 4, 2.0, 0.5) * 2.0 - 1.0;
+        let sharpened = continents.signum() * continents.abs().powf(0.5);
+        let basins = continents + self.tectonic_activity * (sharpened - continents);
+
+        // Folded mountain belts along plate boundaries
+        let ridge_noise = fbm(path.stream("ridges").seed(), 3.0 * x, 3.0 * y, 3.0 * z, 5, 2.0, 0.5);
+        let ridges = (1.0 - (2.0 * ridge_noise - 1.0).abs()).powi(4);
+
+        // Hills and roughness, smoothed by erosion
+        let gain = 0.6 - 0.25 * self.erosion;
+        let detail = fbm(path.stream("detail").seed(), 8.0 * x, 8.0 * y, 8.0 * z, 8, 2.0, gain) * 2.0 - 1.0;
+
+        self.relief * (0.6 * basins + 0.4 * self.tectonic_activity * ridges + 0.2 * detail)
+    }
+
+    /// Whether a latitude and longitude lies under the sea
+    pub fn is_underwater(&self, latitude: f64, longitude: f64) -> bool {
+        self.sea_level.is_some_and(|sea| self.height(latitude, longitude) < sea)
+    }
+
+    /// Heights on an equirectangular grid of `width` by `height` samples
+    pub fn heightmap(&self, width: usize, height: usize) -> Heightmap {
+        let mut map = Heightmap { width, height, heights: Vec::with_capacity(width * height) };
+        for y in 0..height {
+            for x in 0..width {
+                let h = self.height(map.latitude(y), map.longitude(x));
+                map.heights.push(h);
+            }
+        }
+        map
+    }
+
+    /// Fraction of the surface under the sea
+    pub fn ocean_fraction(&self) -> f64 {
+        match self.sea_level {
+            None => 0.0,
+            Some(sea) => {
+                let heights = self.sample_heights();
+                heights.iter().filter(|&&h| h < sea).count() as f64 / heights.len() as f64
+            }
+        }
+    }
+
+    /// Heights at points spread evenly over the sphere on a Fibonacci lattice
+    fn sample_heights(&self) -> Vec<f64> {
+        let golden_angle = 180.0 * (3.0 - 5f64.sqrt());
+        (0..SEA_LEVEL_SAMPLES).map(|i| {
+            let latitude = (1.0 - 2.0 * (i as f64 + 0.5) / SEA_LEVEL_SAMPLES as f64).asin().to_degrees();
+            let longitude = (i as f64 * golden_angle) % 360.0 - 180.0;
+            self.height(latitude, longitude)
+        }).collect()
+    }
+
+    /// Level (m) at which `ocean_depth` m of water, spread over the sphere,
+    /// fills the terrain's basins
+    fn sea_level_for(&self, ocean_depth: f64) -> f64 {
+        let heights = self.sample_heights();
+        let volume = |level: f64| heights.iter().map(|h| (level - h).max(0.0)).sum::<f64>() / heights.len() as f64;
+
+        // Water deep enough to drown every peak lies level above them
+        let lowest = heights.iter().cloned().fold(f64::INFINITY, f64::min);
+        let highest = heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let (mut low, mut high) = (lowest, highest + ocean_depth);
+        for _ in 0..60 {
+            let mid = 0.5 * (low + high);
+            if volume(mid) < ocean_depth { low = mid } else { high = mid }
+        }
+        0.5 * (low + high)
+    }
+}
+
+impl Planet {
+    /// Terrain of a solid planet of `age` billion years; giants have no surface.
+    /// Oceans fill the basins on wet worlds cool enough to keep them, frozen
+    /// over on ice worlds.
+    pub fn surface(&self, age: f64) -> Option<Surface> {
+        if self.planet_type.is_giant() {
+            return None;
+        }
+        let mass = self.physical.mass / 5.972e24;
+
+        // Magma oceans churn constantly
+        let tectonic = match self.planet_type {
+            PlanetType::LavaWorld => 1.0,
+            _ => tectonic_activity(mass, age),
+        };
+        // Wind and water wear down old terrain; on airless worlds only impacts do
+        let weathering = if self.atmosphere.is_some() { 1.0 } else { 0.3 };
+        let erosion = (age / 10.0).clamp(0.0, 1.0) * weathering;
+
+        let ocean_depth = match self.planet_type {
+            PlanetType::DesertWorld | PlanetType::LavaWorld | PlanetType::ChthonianPlanet => 0.0,
+            // Surface water is ~2.3e-4 of the Earth's mass
+            _ => EARTH_OCEAN_DEPTH * self.water_fraction / 2.3e-4 * (mass / (self.physical.radius / 6.371e6).powi(2)),
+        };
+        Some(Surface::new(self.seed, self.physical.radius, self.physical.surface_gravity, tectonic, erosion, ocean_depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::earth_analogue;
+
+    fn earth_like(water_fraction: f64) -> Planet {
+        let mut planet = earth_analogue(1.0, 1.0);
+        planet.water_fraction = water_fraction;
+        planet
+    }
+
+    #[test]
+    fn test_surface_is_seamless_and_deterministic() {
+        let surface = earth_like(2.3e-4).surface(4.6).unwrap();
+
+        // The date line and the poles join up
+        for lat in [-60.0, 0.0, 45.0] {
+            assert!((surface.height(lat, -180.0) - surface.height(lat, 180.0)).abs() < 1e-6);
+        }
+        let pole = surface.height(90.0, 0.0);
+        for lon in [-120.0, 30.0, 170.0] {
+            assert!((surface.height(90.0, lon) - pole).abs() < 1e-6);
+        }
+
+        // Nearby points have nearby heights
+        let step = (surface.height(10.0, 20.0) - surface.height(10.0001, 20.0)).abs();
+        assert!(step < 0.01 * surface.relief, "{}", step);
+
+        // Grids sample the same field as single points
+        let map = surface.heightmap(64, 32);
+        assert_eq!(map.heights.len(), 64 * 32);
+        assert_eq!(map.get(5, 7), surface.height(map.latitude(7), map.longitude(5)));
+
+        let again = earth_like(2.3e-4).surface(4.6).unwrap();
+        assert_eq!(surface.height(12.0, 34.0), again.height(12.0, 34.0));
+        assert_eq!(surface.sea_level, again.sea_level);
+    }
+
+    #[test]
+    fn test_terrain_parameters() {
+        // Earth-like water covers most of the surface; dry worlds have no sea
+        // and water worlds drown everything
+        let earth = earth_like(2.3e-4).surface(4.6).unwrap();
+        let ocean = earth.ocean_fraction();
+        assert!(ocean > 0.4 && ocean < 0.95, "{}", ocean);
+        assert_eq!(earth_like(0.0).surface(4.6).unwrap().sea_level, None);
+        assert!(earth_like(1e-5).surface(4.6).unwrap().ocean_fraction() < ocean);
+        assert_eq!(earth_like(0.1).surface(4.6).unwrap().ocean_fraction(), 1.0);
+
+        // Weak gravity raises taller mountains
+        let mut small = earth_like(2.3e-4);
+        small.physical.surface_gravity /= 3.0;
+        assert!(small.surface(4.6).unwrap().relief > 2.0 * earth.relief);
+
+        // Old, small planets cool into a single stagnant lid
+        assert!(tectonic_activity(1.0, 1.0) > tectonic_activity(1.0, 10.0));
+        assert!(tectonic_activity(0.1, 4.6) < tectonic_activity(1.0, 4.6));
+        assert!(tectonic_activity(5.0, 0.5) <= 1.0);
+
+        // Giants have no surface
+        let giant = Planet::generate_at_distance(999999, 5.0);
+        assert!(giant.surface(4.6).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    Galaxy, GalaxyMorphology, Generate, Position, SolarSystem, Star,
+    small_body_generation::SmallBodyGeneration
+};
+
+const SEEDS: [u64; 8] = [0, 1, 2, 42, 1234, 65_535, 0xDEAD_BEEF, u64::MAX];
+
+fn serialize<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec(value).expect("generated data should serialize")
+}
+
+/// Generate twice and require byte-identical output
+fn assert_reproducible<T: serde::Serialize>(generate: impl Fn() -> T) {
+    assert_eq!(serialize(&generate()), serialize(&generate()));
+}
+
+#[test]
+fn test_stars_are_reproducible() {
+    for seed in SEEDS {
+        assert_reproducible(|| Star::generate_with_seed(seed));
+    }
+}
+
+#[test]
+fn test_solar_systems_are_reproducible() {
+    for seed in SEEDS {
+        assert_reproducible(|| SolarSystem::generate_with_seed(seed));
+    }
+
+    // A wider sweep catches rarer star types and planet counts
+    for seed in 0..500 {
+        assert_reproducible(|| SolarSystem::generate_with_seed(seed));
+    }
+}
+
+#[test]
+fn test_small_bodies_are_reproducible() {
+    for seed in SEEDS {
+        let system = SolarSystem::generate_with_seed(seed);
+        let belt = Position { x: 2.7, y: 0.0, z: 0.0 };
+        assert_reproducible(|| system.generate_small_bodies(belt, 0.5, 10.0));
+    }
+}
+
+#[test]
+fn test_galaxy_regions_are_reproducible() {
+    let morphologies = [
+        GalaxyMorphology::Spiral,
+        GalaxyMorphology::BarredSpiral,
+        GalaxyMorphology::Lenticular,
+        GalaxyMorphology::Elliptical,
+        GalaxyMorphology::Irregular,
+    ];
+
+    for seed in SEEDS {
+        for morphology in morphologies {
+            let mut galaxy = Galaxy::with_morphology(morphology);
+            galaxy.seed = seed;
+            assert_reproducible(|| galaxy.generate_region(8000.0, 120.0, 15.0));
+            assert_reproducible(|| galaxy.generate_region(-2500.0, 900.0, -40.0));
+        }
+    }
+}
+
+#[test]
+fn test_catalogues_are_reproducible() {
+    for seed in SEEDS {
+        let mut galaxy = Galaxy::new();
+        galaxy.seed = seed;
+        let min = Position { x: 7990.0, y: -10.0, z: -10.0 };
+        let max = Position { x: 8010.0, y: 10.0, z: 10.0 };
+        assert_reproducible(|| galaxy.stars_in_volume(min, max));
+
+        let stars = galaxy.stars_in_volume(min, max);
+        for stub in stars.iter().take(5) {
+            assert_reproducible(|| stub.expand(&galaxy));
+        }
+    }
+}
+
+#[test]
+fn test_different_seeds_differ() {
+    let systems: Vec<Vec<u8>> = SEEDS
+        .iter()
+        .map(|&seed| serialize(&SolarSystem::generate_with_seed(seed)))
+        .collect();
+
+    for (i, a) in systems.iter().enumerate() {
+        for b in &systems[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
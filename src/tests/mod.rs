@@ -1,5 +1,55 @@
 pub(crate) mod statistical_tests;
 pub(crate) mod small_body_tests;
+pub(crate) mod determinism_tests;
 
-pub(crate) use statistical_tests::*;
-pub(crate) use small_body_tests::*;
+use crate::{Generate, HostConditions, Planet, PlanetType, SolarSystem};
+use crate::distributions::habitable_zone_range;
+use rand::prelude::*;
+
+/// The Sun today
+pub(crate) fn sun() -> HostConditions {
+    HostConditions {
+        habitable_zone: habitable_zone_range(1.0, 1.0),
+        stellar_temperature: 5772.0,
+        age: 4.6,
+        ultraviolet_flux: 1.0,
+    }
+}
+
+/// Seeds searched for a fixture before giving up
+const FIXTURE_SEEDS: u64 = 10_000;
+
+/// What `generate` makes of the lowest seed whose result satisfies
+/// `predicate`; panics naming the `fixture` if no seed below `FIXTURE_SEEDS` does
+pub(crate) fn first_seed<T>(fixture: &str, generate: impl Fn(u64) -> T, predicate: impl Fn(&T) -> bool) -> T {
+    (0..FIXTURE_SEEDS)
+        .map(generate)
+        .find(|value| predicate(value))
+        .unwrap_or_else(|| panic!("no seed below {} gives {}", FIXTURE_SEEDS, fixture))
+}
+
+/// The planet of the lowest seed that, formed `distance` AU from a Sun-like
+/// star of `metallicity` [Fe/H], satisfies `predicate`
+pub(crate) fn first_planet(fixture: &str, distance: f64, metallicity: f64, predicate: impl Fn(&Planet) -> bool) -> Planet {
+    first_seed(fixture, |seed| Planet::generate_around_star(seed, distance, 1.0, metallicity), predicate)
+}
+
+/// The system of the lowest seed that satisfies `predicate`
+pub(crate) fn first_system(fixture: &str, predicate: impl Fn(&SolarSystem) -> bool) -> SolarSystem {
+    first_seed(fixture, SolarSystem::generate_with_seed, predicate)
+}
+
+/// An Earth analogue of `mass` Earth masses with the Earth's water, day and
+/// tilt, settled at `distance` AU from the Sun and assessed for habitability
+pub(crate) fn earth_analogue(mass: f64, distance: f64) -> Planet {
+    let mut planet = first_planet("a terrestrial planet with an atmosphere", distance, 0.0, |p| {
+        p.planet_type == PlanetType::Terrestrial && p.atmosphere.is_some()
+    });
+    planet.initial_mass = mass;
+    planet.water_fraction = 2.3e-4;
+    planet.rotation_period = 1.0;
+    planet.axial_tilt = 23.44;
+    planet.set_climate(&mut StdRng::seed_from_u64(0), 1.0, 1.0, distance);
+    planet.assess_habitability(&sun());
+    planet
+}
@@ -1,11 +1,37 @@
 use std::collections::HashMap;
 use crate::{
     Generate, Planet, PlanetType,
-    solar_system::{StellarType, Star},
+    solar_system::StellarType,
     SolarSystem,
 };
 
 const SAMPLE_SIZE: usize = 10000;
+const TAXONOMY_SEEDS: u64 = 2000;
+
+const ALL_PLANET_TYPES: [PlanetType; 13] = [
+    PlanetType::Terrestrial,
+    PlanetType::GasGiant,
+    PlanetType::IceGiant,
+    PlanetType::SuperEarth,
+    PlanetType::MiniNeptune,
+    PlanetType::HotJupiter,
+    PlanetType::LavaWorld,
+    PlanetType::OceanWorld,
+    PlanetType::DesertWorld,
+    PlanetType::IceWorld,
+    PlanetType::ChthonianPlanet,
+    PlanetType::CarbonPlanet,
+    PlanetType::DwarfPlanet,
+];
+
+/// Observational class of a planet: Jupiter-like, Neptune-like or rocky
+fn planet_family(planet_type: &PlanetType) -> &'static str {
+    match planet_type {
+        PlanetType::GasGiant | PlanetType::HotJupiter => "Jupiter-like",
+        PlanetType::IceGiant | PlanetType::MiniNeptune => "Neptune-like",
+        _ => "rocky",
+    }
+}
 
 #[derive(Default)]
 struct StarStatistics {
@@ -20,10 +46,10 @@ struct StarStatistics {
 
 impl StarStatistics {
     fn add_system(&mut self, system: &SolarSystem) {
-        *self.type_counts.entry(system.star.stellar_type.clone()).or_insert(0) += 1;
-        self.mass_sum += system.star.physical.mass / 1.989e30; // Convert to solar masses
-        self.mass_squared_sum += (system.star.physical.mass / 1.989e30).powi(2);
-        self.luminosity_sum += system.star.luminosity;
+        *self.type_counts.entry(system.star().stellar_type.clone()).or_insert(0) += 1;
+        self.mass_sum += system.star().physical.mass / 1.989e30; // Convert to solar masses
+        self.mass_squared_sum += (system.star().physical.mass / 1.989e30).powi(2);
+        self.luminosity_sum += system.star().luminosity;
         self.planet_count_sum += system.planets.len();
         self.habitable_planet_count += system.habitable_planets().len();
         self.total_stars += 1;
@@ -83,6 +109,13 @@ impl PlanetStatistics {
         *self.type_counts.get(planet_type).unwrap_or(&0) as f64 / self.total_planets as f64
     }
 
+    fn family_frequency(&self, family: &str) -> f64 {
+        ALL_PLANET_TYPES.iter()
+            .filter(|planet_type| planet_family(planet_type) == family)
+            .map(|planet_type| self.type_frequency(planet_type))
+            .sum()
+    }
+
     fn median_orbital_distance(&self) -> f64 {
         let mut distances = self.orbital_distances.clone();
         distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
@@ -136,7 +169,7 @@ fn test_planet_mass_distribution() {
     for _ in 0..SAMPLE_SIZE {
         let system = SolarSystem::generate();
         for planet in &system.planets {
-            let distance = (planet.position.x.powi(2) + planet.position.y.powi(2)).sqrt() / 1.496e11;
+            let distance = (planet.position().x.powi(2) + planet.position().y.powi(2)).sqrt() / 1.496e11;
             stats.add_planet(planet, distance);
         }
     }
@@ -154,17 +187,17 @@ fn test_planet_mass_distribution() {
 
     // Expected frequencies based on Kepler data
     let expected_frequencies = [
-        (PlanetType::Terrestrial, 0.35),  // Super-Earths and smaller
-        (PlanetType::IceGiant, 0.25),     // Neptune-like
-        (PlanetType::GasGiant, 0.40),     // Jupiter-like
+        ("rocky", 0.35),         // Super-Earths and smaller
+        ("Neptune-like", 0.25),  // Ice giants and mini-Neptunes
+        ("Jupiter-like", 0.40),  // Gas giants, hot or not
     ];
 
-    for (planet_type, expected_freq) in expected_frequencies.iter() {
-        let actual_freq = stats.type_frequency(planet_type);
+    for (family, expected_freq) in expected_frequencies.iter() {
+        let actual_freq = stats.family_frequency(family);
         let tolerance = 0.15; // Allow 15% deviation due to observational bias
         assert!((actual_freq - expected_freq).abs() < tolerance,
-            "Planet type {:?} frequency {:.2} differs from expected {:.2} by more than {:.2}",
-            planet_type, actual_freq, expected_freq, tolerance);
+            "Planet family {} frequency {:.2} differs from expected {:.2} by more than {:.2}",
+            family, actual_freq, expected_freq, tolerance);
     }
 
     // Test mass distribution properties
@@ -173,6 +206,75 @@ fn test_planet_mass_distribution() {
         stats.mean_mass());
 }
 
+#[test]
+fn test_planet_taxonomy() {
+    let mut stats = PlanetStatistics::default();
+    let mut planets = Vec::new();
+
+    // Fixed seeds, so that a missing class can be reproduced
+    for seed in 0..TAXONOMY_SEEDS {
+        let system = SolarSystem::generate_with_seed(seed);
+        for planet in system.planets {
+            stats.add_planet(&planet, planet.orbit.semi_major_axis);
+            planets.push(planet);
+        }
+    }
+
+    // Every class turns up in the sample
+    for planet_type in ALL_PLANET_TYPES.iter() {
+        assert!(stats.type_frequency(planet_type) > 0.0, "No {:?} generated", planet_type);
+    }
+    // Rare outcomes stay rare
+    assert!(stats.type_frequency(&PlanetType::CarbonPlanet) < 0.05);
+    assert!(stats.type_frequency(&PlanetType::ChthonianPlanet) < 0.05);
+
+    let earth_radius = 6.371e6;
+    for planet in &planets {
+        let mass = planet.physical.mass / 5.972e24;
+        let radius = planet.physical.radius / earth_radius;
+        let temperature = planet.physical.surface_temperature;
+        match planet.planet_type {
+            PlanetType::DwarfPlanet => assert!(mass < 0.1 && planet.atmosphere.is_none()),
+            PlanetType::SuperEarth => assert!((2.0..=10.0).contains(&mass)),
+            PlanetType::Terrestrial => assert!(mass < 2.0 && temperature < 400.0),
+            PlanetType::MiniNeptune => assert!(radius > 1.5 && mass <= 10.0),
+            PlanetType::HotJupiter => assert!(radius > 4.0),
+            PlanetType::LavaWorld => assert!(temperature > 1100.0),
+            PlanetType::IceWorld => assert!(temperature < 260.0),
+            PlanetType::OceanWorld => assert!(planet.water_fraction > 0.01 && temperature < 420.0),
+            PlanetType::DesertWorld => assert!(planet.water_fraction < 1e-5 || temperature > 350.0),
+            PlanetType::ChthonianPlanet => assert!(planet.atmosphere.is_none()),
+            PlanetType::CarbonPlanet => assert!(planet.composition.other >= 0.5),
+            PlanetType::GasGiant | PlanetType::IceGiant => assert!(radius > 2.0),
+        }
+    }
+
+    // Giants are wrapped in hydrogen; solid worlds keep only what escape allows
+    for planet in &planets {
+        if let Some(atmosphere) = &planet.atmosphere {
+            let gas = &atmosphere.composition;
+            let total = gas.nitrogen + gas.oxygen + gas.carbon_dioxide + gas.water + gas.methane
+                + gas.ammonia + gas.argon + gas.hydrogen + gas.helium + gas.sulfur_dioxide;
+            assert!((total - 1.0).abs() < 1e-9, "{:?}", gas);
+            if planet.planet_type.is_giant() {
+                assert!(gas.hydrogen > 0.7);
+            } else {
+                assert!(gas.hydrogen < 0.06);  // at most the captured nebular gas
+            }
+        }
+    }
+
+    // Envelopes make mini-Neptunes far less dense than rocky super-Earths
+    let mean_density = |planet_type: PlanetType| {
+        let matching: Vec<f64> = planets.iter()
+            .filter(|p| p.planet_type == planet_type)
+            .map(|p| p.physical.density)
+            .collect();
+        matching.iter().sum::<f64>() / matching.len() as f64
+    };
+    assert!(mean_density(PlanetType::MiniNeptune) < 0.5 * mean_density(PlanetType::SuperEarth));
+}
+
 #[test]
 fn test_system_properties() {
     let mut stats = StarStatistics::default();
@@ -218,7 +320,7 @@ fn test_orbital_distributions() {
     for _ in 0..SAMPLE_SIZE {
         let system = SolarSystem::generate();
         for planet in &system.planets {
-            let distance = (planet.position.x.powi(2) + planet.position.y.powi(2)).sqrt() / 1.496e11;
+            let distance = (planet.position().x.powi(2) + planet.position().y.powi(2)).sqrt() / 1.496e11;
             stats.add_planet(planet, distance);
         }
     }
@@ -257,10 +359,11 @@ fn test_orbital_distributions() {
     let medium_ratio = medium_orbits as f64 / stats.total_planets as f64;
     let far_ratio = far_orbits as f64 / stats.total_planets as f64;
 
-    assert!(close_ratio > 0.2 && close_ratio < 0.4,
+    // Red dwarfs, half of all stars, keep all of their 0-5 planets inside 0.5 AU
+    assert!(close_ratio > 0.45 && close_ratio < 0.65,
         "Close orbit ratio {:.2} is outside expected range", close_ratio);
-    assert!(medium_ratio > 0.3 && medium_ratio < 0.7,
+    assert!(medium_ratio > 0.2 && medium_ratio < 0.45,
         "Medium orbit ratio {:.2} is outside expected range", medium_ratio);
-    assert!(far_ratio > 0.1 && far_ratio < 0.4,
+    assert!(far_ratio > 0.05 && far_ratio < 0.25,
         "Far orbit ratio {:.2} is outside expected range", far_ratio);
 }